@@ -0,0 +1,89 @@
+//! Rough timings of the rules core, comparing the bitboard path checks and the shortest path
+//! shortcut used by move generation with the depth-first search over the fence array that
+//! they replaced
+use std::time::{Duration, Instant};
+
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::game::{Axis, Board, PlayerColor};
+
+/// Collects positions from random games and times the fence legality checks on all of them
+pub fn run(positions: usize, seed: u64) {
+    let boards = random_positions(positions, seed);
+    println!("Collected {} positions", boards.len());
+
+    let (dfs_legal, dfs_time) = time(|| count_legal_fences(&boards, Board::is_fence_legal_by_dfs));
+    let (legal, bitboard_time) = time(|| {
+        count_legal_fences(&boards, |board, player, axis, pos| {
+            board.is_fence_move_legal(player, axis, pos).is_ok()
+        })
+    });
+    let (generated, generation_time) = time(|| {
+        boards
+            .iter()
+            .map(|(board, player)| board.legal_fence_moves(*player).len())
+            .sum::<usize>()
+    });
+    assert_eq!(
+        legal, dfs_legal,
+        "Both checks should find the same fences legal"
+    );
+    assert_eq!(
+        generated, dfs_legal,
+        "Generation should find the same fences legal"
+    );
+    println!("Fence legality, fence array DFS: {dfs_time:?}");
+    println!("Fence legality, bitboards:       {bitboard_time:?}");
+    println!("Fence legality, path shortcut:   {generation_time:?}");
+
+    let (_, moves_time) = time(|| {
+        boards
+            .iter()
+            .map(|(board, player)| board.legal_moves(*player).len())
+            .sum::<usize>()
+    });
+    println!("Move generation:                 {moves_time:?}");
+}
+
+fn time<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let result = f();
+    (result, start.elapsed())
+}
+
+fn random_positions(count: usize, seed: u64) -> Vec<(Board, PlayerColor)> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut boards = vec![];
+    while boards.len() < count {
+        Board::default().play_random_game(
+            PlayerColor::White,
+            usize::MAX,
+            &mut rng,
+            |board, turn| {
+                if board.is_game_won().is_none() {
+                    boards.push((board.clone(), turn));
+                }
+            },
+        );
+    }
+    boards.truncate(count);
+    boards
+}
+
+fn count_legal_fences(
+    boards: &[(Board, PlayerColor)],
+    is_legal: impl Fn(&Board, PlayerColor, Axis, (usize, usize)) -> bool,
+) -> usize {
+    let mut count = 0;
+    for (board, player) in boards {
+        let slots = board.size() - 1;
+        for y in 0..slots {
+            for x in 0..slots {
+                for axis in [Axis::Horizontal, Axis::Vertical] {
+                    count += usize::from(is_legal(board, *player, axis, (x, y)));
+                }
+            }
+        }
+    }
+    count
+}
@@ -0,0 +1,671 @@
+use core::{fmt::Display, str::FromStr};
+use std::{path::PathBuf, time::Duration};
+
+use crate::game::{
+    board::{
+        mcts::MctsConfig,
+        render::{Charset, Labels, RenderOptions},
+        search_and_evaluation::DEFAULT_DEPTH,
+    },
+    clock::{Increment, TimeControl},
+    state::DrawRules,
+    BoardConfig, PlayerColor,
+};
+
+pub const USAGE: &str = "\
+Usage: corridor [play] [OPTIONS]
+       corridor replay <FILE>
+       corridor bench [--positions <N>] [--seed <N>]
+       corridor perft <DEPTH> [--position <POS>] [--divide]
+
+Options:
+  --white <PLAYER>    Who plays white (default: ab)
+  --black <PLAYER>    Who plays black (default: ab)
+  --red <PLAYER>, --green <PLAYER>
+                      Who plays red and green in four-player games (default: ab)
+  --players <N>       Number of players, 2 or 4 (default: 2)
+  --size <N>          Squares along each side of the board, odd from 3 to 11 (default: 9)
+  --fences <N>        Fences each player starts with, up to 64 (default: 10, or 5 with four
+                      players)
+  --position <POS>    Start from this position, like `e1 e9 e3h,d5v 9 9 w`
+  --seed <N>          Seed for the random players and MCTS
+  --moves <MOVES>     Space separated moves to play before the game starts
+  --record <FILE>     Save a record of the game to this file
+  --tui               Play full-screen, picking moves with the arrow keys
+  --ascii             Draw the board with plain ASCII characters
+  --no-color          Draw the board without colors
+  --no-labels         Leave the coordinates off the board
+  --slot-labels       Name the fence slots on the board, like `e3` for `e3h` and `e3v`
+  --flip              Draw the board from Black's side
+  --repetitions <N>   Draw when a position comes up N times, at least 2 or 0 for never
+                      (default: 3)
+  --max-plies <N>     Draw after N moves in total, at least 1 (default: no limit)
+  --clock <TIME>      Time control for every player, like `5+3` (see below)
+  --white-clock <TIME>, --black-clock <TIME>, --red-clock <TIME>, --green-clock <TIME>
+                      Time control for one player, overriding `--clock`
+  -h, --help          Print this message
+
+Players:
+  human                                       Moves are typed in
+  random                                      Picks a random legal move
+  ab[:depth=N,time=MS]                        Alpha-beta search
+  mcts[:iterations=N,exploration=F,bias=F]    Monte Carlo tree search
+
+Time controls:
+  MIN          Sudden death, MIN minutes for the whole game
+  MIN+SEC      Fischer, SEC seconds are added after every move
+  MINdSEC      Bronstein, up to SEC seconds of each move are given back
+
+The board is drawn in plain ASCII without colors when the output isn't a terminal, and without
+colors when NO_COLOR is set.";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlayerSpec {
+    Human,
+    Random,
+    AlphaBeta {
+        depth: Option<u32>,
+        time: Option<Duration>,
+    },
+    Mcts(MctsConfig),
+}
+
+impl Default for PlayerSpec {
+    fn default() -> Self {
+        Self::AlphaBeta {
+            depth: Some(DEFAULT_DEPTH),
+            time: None,
+        }
+    }
+}
+
+impl Display for PlayerSpec {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Human => write!(f, "human"),
+            Self::Random => write!(f, "random"),
+            Self::AlphaBeta { depth, time } => {
+                write!(f, "ab")?;
+                let depth = depth.map(|depth| format!("depth={depth}"));
+                let time = time.map(|time| format!("time={}", time.as_millis()));
+                let parameters: Vec<_> = depth.into_iter().chain(time).collect();
+                if !parameters.is_empty() {
+                    write!(f, ":{}", parameters.join(","))?;
+                }
+                Ok(())
+            }
+            Self::Mcts(config) => write!(
+                f,
+                "mcts:iterations={},exploration={},bias={}",
+                config.iterations, config.exploration, config.shortest_path_bias
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PlayOptions {
+    pub white: PlayerSpec,
+    pub black: PlayerSpec,
+    pub red: PlayerSpec,
+    pub green: PlayerSpec,
+    pub board: BoardConfig,
+    /// Position string to start from, which overrides `board`
+    pub position: Option<String>,
+    pub seed: Option<u64>,
+    pub moves: Vec<String>,
+    pub record: Option<PathBuf>,
+    pub draw_rules: DrawRules,
+    /// Whether to play in the full-screen interface instead of printing the board every move
+    pub tui: bool,
+    /// How the board is drawn when it is printed
+    pub render: RenderOptions,
+    /// Time controls of the players in the order of `PlayerColor::ALL`, if the game is timed
+    pub clocks: Option<Vec<TimeControl>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Play(Box<PlayOptions>),
+    /// Replays the game record in the file
+    Replay(PathBuf),
+    /// Times the rules core on positions from random games
+    Bench {
+        positions: usize,
+        seed: u64,
+    },
+    /// Counts the move sequences of some length from a position
+    Perft {
+        depth: u32,
+        position: Option<String>,
+        divide: bool,
+    },
+    Help,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CliError {
+    UnknownCommand(String),
+    UnknownOption(String),
+    MissingValue(String),
+    InvalidValue {
+        option: String,
+        value: String,
+    },
+    UnknownPlayer(String),
+    InvalidPlayerParameter {
+        player: String,
+        parameter: String,
+    },
+    /// Only some of the players were given a time control
+    OneSidedClock,
+    /// A time control was given to a player who isn't in the game
+    ClockForAbsentPlayer(PlayerColor),
+}
+
+impl Display for CliError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::UnknownCommand(command) => write!(f, "unknown command `{command}`"),
+            Self::UnknownOption(option) => write!(f, "unknown option `{option}`"),
+            Self::MissingValue(option) => write!(f, "option `{option}` requires a value"),
+            Self::InvalidValue { option, value } => {
+                write!(f, "invalid value `{value}` for option `{option}`")
+            }
+            Self::UnknownPlayer(player) => write!(f, "unknown player `{player}`"),
+            Self::InvalidPlayerParameter { player, parameter } => {
+                write!(f, "invalid parameter `{parameter}` for player `{player}`")
+            }
+            Self::OneSidedClock => write!(f, "every player needs a time control"),
+            Self::ClockForAbsentPlayer(player) => {
+                write!(f, "there is no {player} player to give a time control to")
+            }
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+/// Parses the command line arguments, not including the program name
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Command, CliError> {
+    let mut args = args.into_iter().peekable();
+    let command = args
+        .next_if(|arg| !arg.starts_with('-'))
+        .unwrap_or_else(|| "play".to_owned());
+    match command.as_str() {
+        "play" => parse_play_options(args),
+        "replay" => args
+            .next()
+            .map(|path| Command::Replay(path.into()))
+            .ok_or(CliError::MissingValue(command)),
+        "bench" => parse_bench_options(args),
+        "perft" => parse_perft_options(args),
+        _ => Err(CliError::UnknownCommand(command)),
+    }
+}
+
+/// Pairs up the options in `args` with their values, returning `None` if help was asked for
+///
+/// The `flags` take no value, so they are paired with an empty string.
+fn option_values(
+    mut args: impl Iterator<Item = String>,
+    known: &[&str],
+    flags: &[&str],
+) -> Result<Option<Vec<(String, String)>>, CliError> {
+    let mut values = vec![];
+    while let Some(option) = args.next() {
+        if option == "-h" || option == "--help" {
+            return Ok(None);
+        }
+        if flags.contains(&option.as_str()) {
+            values.push((option, String::new()));
+            continue;
+        }
+        if !known.contains(&option.as_str()) {
+            return Err(CliError::UnknownOption(option));
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| CliError::MissingValue(option.clone()))?;
+        values.push((option, value));
+    }
+    Ok(Some(values))
+}
+
+fn invalid_value(option: &str, value: &str) -> CliError {
+    CliError::InvalidValue {
+        option: option.to_owned(),
+        value: value.to_owned(),
+    }
+}
+
+fn parse_play_options(args: impl Iterator<Item = String>) -> Result<Command, CliError> {
+    let known = [
+        "--white",
+        "--black",
+        "--red",
+        "--green",
+        "--players",
+        "--size",
+        "--fences",
+        "--position",
+        "--seed",
+        "--moves",
+        "--record",
+        "--repetitions",
+        "--max-plies",
+        "--clock",
+        "--white-clock",
+        "--black-clock",
+        "--red-clock",
+        "--green-clock",
+    ];
+    let flags = [
+        "--tui",
+        "--ascii",
+        "--no-color",
+        "--no-labels",
+        "--slot-labels",
+        "--flip",
+    ];
+    let Some(values) = option_values(args, &known, &flags)? else {
+        return Ok(Command::Help);
+    };
+    let mut options = PlayOptions {
+        render: RenderOptions::detect(),
+        ..PlayOptions::default()
+    };
+    let (mut fences, mut clock, mut player_clocks) = (None, None, [None; 4]);
+    for (option, value) in values {
+        let invalid_value = || invalid_value(&option, &value);
+        match option.as_str() {
+            "--white" => options.white = parse_player(&value)?,
+            "--black" => options.black = parse_player(&value)?,
+            "--red" => options.red = parse_player(&value)?,
+            "--green" => options.green = parse_player(&value)?,
+            "--players" => {
+                options.board.players = parse_valid(&value, |&players| {
+                    BoardConfig::is_valid_player_count(players)
+                })
+                .ok_or_else(invalid_value)?;
+            }
+            "--size" => {
+                options.board.size = parse_valid(&value, |&size| BoardConfig::is_valid_size(size))
+                    .ok_or_else(invalid_value)?;
+            }
+            "--fences" => {
+                fences = parse_valid(&value, |&fences| BoardConfig::is_valid_fence_count(fences))
+                    .map(Some)
+                    .ok_or_else(invalid_value)?;
+            }
+            "--position" => options.position = Some(value),
+            "--seed" => options.seed = Some(value.parse().map_err(|_| invalid_value())?),
+            "--moves" => options.moves = value.split_whitespace().map(String::from).collect(),
+            "--record" => options.record = Some(value.into()),
+            "--tui" => options.tui = true,
+            "--ascii" => options.render.charset = Charset::Ascii,
+            "--no-color" => options.render.color = false,
+            "--no-labels" => options.render.labels = Labels::Off,
+            "--slot-labels" => options.render.labels = Labels::SquaresAndSlots,
+            "--flip" => options.render.flipped = true,
+            "--repetitions" => {
+                // Any position has come up once, so only 0 for never or at least 2 make sense
+                let repetitions =
+                    parse_valid(&value, |&count| count != 1).ok_or_else(invalid_value)?;
+                options.draw_rules.repetitions = Some(repetitions).filter(|&count| count > 0);
+            }
+            "--max-plies" => {
+                options.draw_rules.max_plies = parse_valid(&value, |&plies| plies > 0)
+                    .map(Some)
+                    .ok_or_else(invalid_value)?;
+            }
+            "--clock" => clock = Some(parse_time_control(&value).ok_or_else(invalid_value)?),
+            "--white-clock" | "--black-clock" | "--red-clock" | "--green-clock" => {
+                let player = match option.as_str() {
+                    "--white-clock" => PlayerColor::White,
+                    "--black-clock" => PlayerColor::Black,
+                    "--red-clock" => PlayerColor::Red,
+                    _ => PlayerColor::Green,
+                };
+                player_clocks[player as usize] =
+                    Some(parse_time_control(&value).ok_or_else(invalid_value)?);
+            }
+            _ => unreachable!(),
+        }
+    }
+    let players = options.board.players;
+    options.board.fences = fences.unwrap_or_else(|| BoardConfig::standard_fences(players));
+    options.clocks = combine_clocks(clock, &player_clocks, players)?;
+    Ok(Command::Play(Box::new(options)))
+}
+
+/// Parses `value`, as long as the result `is_valid`
+fn parse_valid<T: FromStr>(value: &str, is_valid: impl FnOnce(&T) -> bool) -> Option<T> {
+    value.parse().ok().filter(is_valid)
+}
+
+/// The time controls of the first `players` colors, each having their own from `player_clocks`
+/// or else the `shared` one, as long as either all of them or none of them have one and no
+/// other color has one
+fn combine_clocks(
+    shared: Option<TimeControl>,
+    player_clocks: &[Option<TimeControl>; 4],
+    players: usize,
+) -> Result<Option<Vec<TimeControl>>, CliError> {
+    if let Some(&absent) = PlayerColor::ALL[players..]
+        .iter()
+        .find(|&&player| player_clocks[player as usize].is_some())
+    {
+        return Err(CliError::ClockForAbsentPlayer(absent));
+    }
+    let clocks: Vec<_> = player_clocks[..players]
+        .iter()
+        .map(|clock| clock.or(shared))
+        .collect();
+    if clocks.iter().all(Option::is_some) {
+        Ok(Some(clocks.into_iter().flatten().collect()))
+    } else if clocks.iter().all(Option::is_none) {
+        Ok(None)
+    } else {
+        Err(CliError::OneSidedClock)
+    }
+}
+
+/// Parses a time control like `5`, `5+3` or `5d3`, see `USAGE`
+fn parse_time_control(value: &str) -> Option<TimeControl> {
+    // Negative, infinite and too long durations are all rejected by `try_from_secs_f64`
+    let duration = |value: &str, unit: f64| {
+        value
+            .parse::<f64>()
+            .ok()
+            .and_then(|count| Duration::try_from_secs_f64(count * unit).ok())
+    };
+    let (base, increment) = if let Some((base, increment)) = value.split_once('+') {
+        (base, Increment::Fischer(duration(increment, 1.0)?))
+    } else if let Some((base, delay)) = value.split_once('d') {
+        (base, Increment::Bronstein(duration(delay, 1.0)?))
+    } else {
+        (value, Increment::SuddenDeath)
+    };
+    let base = duration(base, 60.0)?;
+    (!base.is_zero()).then_some(TimeControl { base, increment })
+}
+
+fn parse_bench_options(args: impl Iterator<Item = String>) -> Result<Command, CliError> {
+    let Some(values) = option_values(args, &["--positions", "--seed"], &[])? else {
+        return Ok(Command::Help);
+    };
+    let (mut positions, mut seed) = (200, 0);
+    for (option, value) in values {
+        match option.as_str() {
+            "--positions" => {
+                positions = value.parse().map_err(|_| invalid_value(&option, &value))?;
+            }
+            "--seed" => seed = value.parse().map_err(|_| invalid_value(&option, &value))?,
+            _ => unreachable!(),
+        }
+    }
+    Ok(Command::Bench { positions, seed })
+}
+
+fn parse_perft_options(mut args: impl Iterator<Item = String>) -> Result<Command, CliError> {
+    let depth = args
+        .next()
+        .ok_or_else(|| CliError::MissingValue("perft".to_owned()))?;
+    // Nothing is played at depth 0, so there would be no moves to split the count up by
+    let depth =
+        parse_valid(&depth, |&depth| depth > 0).ok_or_else(|| invalid_value("perft", &depth))?;
+    let Some(values) = option_values(args, &["--position"], &["--divide"])? else {
+        return Ok(Command::Help);
+    };
+    let (mut position, mut divide) = (None, false);
+    for (option, value) in values {
+        match option.as_str() {
+            "--position" => position = Some(value),
+            "--divide" => divide = true,
+            _ => unreachable!(),
+        }
+    }
+    Ok(Command::Perft {
+        depth,
+        position,
+        divide,
+    })
+}
+
+/// Parses a player like `ab:depth=4,time=500`
+fn parse_player(spec: &str) -> Result<PlayerSpec, CliError> {
+    let (name, parameters) = spec.split_once(':').unwrap_or((spec, ""));
+    let mut player = match name {
+        "human" => PlayerSpec::Human,
+        "random" => PlayerSpec::Random,
+        "ab" => PlayerSpec::default(),
+        "mcts" => PlayerSpec::Mcts(MctsConfig::default()),
+        _ => return Err(CliError::UnknownPlayer(name.to_owned())),
+    };
+    // Kept apart from the default depth, which a time limit alone replaces
+    let mut depth = None;
+    for parameter in parameters
+        .split(',')
+        .filter(|parameter| !parameter.is_empty())
+    {
+        let invalid = || CliError::InvalidPlayerParameter {
+            player: name.to_owned(),
+            parameter: parameter.to_owned(),
+        };
+        let (key, value) = parameter.split_once('=').ok_or_else(invalid)?;
+        match (&mut player, key) {
+            (PlayerSpec::AlphaBeta { .. }, "depth") => {
+                depth = Some(
+                    value
+                        .parse()
+                        .ok()
+                        .filter(|depth| *depth > 0)
+                        .ok_or_else(invalid)?,
+                );
+            }
+            (PlayerSpec::AlphaBeta { time, .. }, "time") => {
+                *time = Some(Duration::from_millis(value.parse().map_err(|_| invalid())?));
+            }
+            (PlayerSpec::Mcts(config), "iterations") => {
+                config.iterations = value.parse().map_err(|_| invalid())?;
+            }
+            (PlayerSpec::Mcts(config), "exploration") => {
+                config.exploration = value
+                    .parse()
+                    .ok()
+                    .filter(|exploration: &f64| exploration.is_finite() && *exploration >= 0.0)
+                    .ok_or_else(invalid)?;
+            }
+            (PlayerSpec::Mcts(config), "bias") => {
+                config.shortest_path_bias = value
+                    .parse()
+                    .ok()
+                    .filter(|bias| (0.0..=1.0).contains(bias))
+                    .ok_or_else(invalid)?;
+            }
+            _ => return Err(invalid()),
+        }
+    }
+    if let PlayerSpec::AlphaBeta {
+        depth: search_depth,
+        time,
+    } = &mut player
+    {
+        // A time limit alone means searching as deep as the time allows
+        if depth.is_some() || time.is_some() {
+            *search_depth = depth;
+        }
+    }
+    Ok(player)
+}
+
+#[test]
+fn parses_players_per_color() {
+    let args = ["play", "--white", "human", "--black", "ab:depth=4"];
+    let Ok(Command::Play(options)) = parse_args(args.map(String::from)) else {
+        panic!("Arguments should parse");
+    };
+    assert_eq!(options.white, PlayerSpec::Human);
+    assert_eq!(
+        options.black,
+        PlayerSpec::AlphaBeta {
+            depth: Some(4),
+            time: None
+        }
+    );
+
+    let time = Some(Duration::from_millis(500));
+    assert_eq!(
+        parse_player("ab:time=500"),
+        Ok(PlayerSpec::AlphaBeta { depth: None, time })
+    );
+    for spec in ["ab:time=500,depth=3", "ab:depth=3,time=500"] {
+        assert_eq!(
+            parse_player(spec),
+            Ok(PlayerSpec::AlphaBeta {
+                depth: Some(3),
+                time
+            })
+        );
+    }
+    assert_eq!(
+        parse_player("ab:depth=0"),
+        Err(CliError::InvalidPlayerParameter {
+            player: "ab".to_owned(),
+            parameter: "depth=0".to_owned()
+        })
+    );
+    for exploration in ["-1", "NaN", "inf"] {
+        assert_eq!(
+            parse_player(&format!("mcts:exploration={exploration}")),
+            Err(CliError::InvalidPlayerParameter {
+                player: "mcts".to_owned(),
+                parameter: format!("exploration={exploration}")
+            })
+        );
+    }
+}
+
+#[test]
+fn parses_board_configs() {
+    let args = ["--size", "7", "--fences", "6", "--ascii", "--flip"];
+    let Ok(Command::Play(options)) = parse_args(args.map(String::from)) else {
+        panic!("Arguments should parse");
+    };
+    assert_eq!(
+        options.board,
+        BoardConfig {
+            size: 7,
+            fences: 6,
+            players: 2
+        }
+    );
+    assert_eq!(options.render.charset, Charset::Ascii);
+    assert!(options.render.flipped);
+    let args = ["--players", "4", "--red", "random"];
+    let Ok(Command::Play(options)) = parse_args(args.map(String::from)) else {
+        panic!("Arguments should parse");
+    };
+    assert_eq!(options.board, BoardConfig::with_players(4));
+    assert_eq!(options.red, PlayerSpec::Random);
+    assert_eq!(
+        parse_args(["--players", "3"].map(String::from)),
+        Err(invalid_value("--players", "3"))
+    );
+    for fences in ["65", "-1", "many"] {
+        assert_eq!(
+            parse_args(["--fences", fences].map(String::from)),
+            Err(invalid_value("--fences", fences))
+        );
+    }
+    for size in ["8", "1", "13"] {
+        assert_eq!(
+            parse_args(["--size", size].map(String::from)),
+            Err(invalid_value("--size", size))
+        );
+    }
+}
+
+#[test]
+fn parses_draw_rules() {
+    let args = ["play", "--repetitions", "0", "--tui", "--max-plies", "200"];
+    let Ok(Command::Play(options)) = parse_args(args.map(String::from)) else {
+        panic!("Arguments should parse");
+    };
+    assert_eq!(
+        options.draw_rules,
+        DrawRules {
+            repetitions: None,
+            max_plies: Some(200)
+        }
+    );
+    assert!(options.tui);
+    for (option, value) in [("--repetitions", "1"), ("--max-plies", "0")] {
+        assert_eq!(
+            parse_args([option, value].map(String::from)),
+            Err(invalid_value(option, value))
+        );
+    }
+}
+
+#[test]
+fn parses_time_controls() {
+    let args = ["--clock", "5+3", "--black-clock", "2.5d2"];
+    let Ok(Command::Play(options)) = parse_args(args.map(String::from)) else {
+        panic!("Arguments should parse");
+    };
+    assert_eq!(
+        options.clocks,
+        Some(vec![
+            TimeControl {
+                base: Duration::from_mins(5),
+                increment: Increment::Fischer(Duration::from_secs(3))
+            },
+            TimeControl {
+                base: Duration::from_secs(150),
+                increment: Increment::Bronstein(Duration::from_secs(2))
+            }
+        ])
+    );
+    assert_eq!(
+        parse_args(["--white-clock", "5"].map(String::from)),
+        Err(CliError::OneSidedClock)
+    );
+    assert_eq!(
+        parse_args(["--clock", "5", "--red-clock", "3"].map(String::from)),
+        Err(CliError::ClockForAbsentPlayer(PlayerColor::Red))
+    );
+    let args = ["--players", "4", "--clock", "5", "--green-clock", "3"];
+    let Ok(Command::Play(options)) = parse_args(args.map(String::from)) else {
+        panic!("Arguments should parse");
+    };
+    let clocks = options.clocks.expect("Every player has a clock");
+    assert_eq!(clocks.len(), 4);
+    assert_eq!(clocks[3].base, Duration::from_mins(3));
+    assert_eq!(parse_time_control("0+5"), None);
+    assert_eq!(parse_time_control("5+x"), None);
+    for huge in ["1e18", "1e300", "5+1e300", "inf"] {
+        assert_eq!(
+            parse_args(["--clock", huge].map(String::from)),
+            Err(invalid_value("--clock", huge))
+        );
+    }
+}
+
+#[test]
+fn parses_perft_depths() {
+    let args = ["perft", "3", "--divide"];
+    assert_eq!(
+        parse_args(args.map(String::from)),
+        Ok(Command::Perft {
+            depth: 3,
+            position: None,
+            divide: true
+        })
+    );
+    assert_eq!(
+        parse_args(["perft", "0"].map(String::from)),
+        Err(invalid_value("perft", "0"))
+    );
+}
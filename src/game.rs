@@ -1,18 +1,43 @@
 pub mod board;
+pub mod clock;
+pub mod notation;
+pub mod record;
+pub mod state;
 use core::fmt::Display;
 
-pub use board::{fence_move, pawn_move, Board, LegalMove, MoveMakeFail};
+pub use board::{explanation::IllegalMove, Board, BoardConfig, LegalMove, MoveMakeFail};
+pub use state::Game;
 
+/// The players, White starting at the bottom and Black at the top, with Red on the left and
+/// Green on the right only taking part in four-player games
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PlayerColor {
     White,
     Black,
+    Red,
+    Green,
+}
+impl PlayerColor {
+    /// Every player, in the order used to index per-player arrays
+    pub const ALL: [Self; 4] = [Self::White, Self::Black, Self::Red, Self::Green];
+
+    /// The letter standing for the player in position strings and on the board
+    pub const fn letter(self) -> char {
+        match self {
+            Self::White => 'w',
+            Self::Black => 'b',
+            Self::Red => 'r',
+            Self::Green => 'g',
+        }
+    }
 }
 impl Display for PlayerColor {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::White => write!(f, "white"),
             Self::Black => write!(f, "black"),
+            Self::Red => write!(f, "red"),
+            Self::Green => write!(f, "green"),
         }
     }
 }
@@ -54,71 +79,175 @@ pub enum Axis {
     Vertical,
 }
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::enum_variant_names)]
 pub enum Move {
     MovePlayer(Direction, Option<Direction>),
+    PawnTo((usize, usize)),
     PlaceFence(Axis, (usize, usize)),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TryIntoMoveError {
-    UnrecognizedChar,
-    UnexpectedEndOfString,
+    /// The move ended where `expected` should have come
+    UnexpectedEnd { expected: String },
+    /// The character at `index` (counting characters from 0) isn't `expected`
+    UnexpectedChar {
+        index: usize,
+        found: char,
+        expected: String,
+    },
+}
+
+impl Display for TryIntoMoveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnexpectedEnd { expected } => {
+                write!(f, "the move ended where {expected} was expected")
+            }
+            Self::UnexpectedChar {
+                index,
+                found,
+                expected,
+            } => write!(
+                f,
+                "unexpected `{found}` at character {}, expected {expected}",
+                index + 1
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TryIntoMoveError {}
+
+/// The characters of a move being parsed, remembering where each one was for errors
+struct MoveChars<'a> {
+    chars: core::iter::Enumerate<core::str::Chars<'a>>,
 }
+
+impl<'a> MoveChars<'a> {
+    fn new(value: &'a str) -> Self {
+        Self {
+            chars: value.chars().enumerate(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.chars.clone().next().is_none()
+    }
+
+    /// Reads the next character with `parse`, which is given it in lowercase and returns `None`
+    /// if it isn't `expected`
+    fn parse<T>(
+        &mut self,
+        expected: &str,
+        parse: impl FnOnce(char) -> Option<T>,
+    ) -> Result<T, TryIntoMoveError> {
+        let (index, found) = self
+            .chars
+            .next()
+            .ok_or_else(|| TryIntoMoveError::UnexpectedEnd {
+                expected: expected.to_owned(),
+            })?;
+        parse(found.to_ascii_lowercase()).ok_or_else(|| TryIntoMoveError::UnexpectedChar {
+            index,
+            found,
+            expected: expected.to_owned(),
+        })
+    }
+
+    /// Reads one of the first `count` letters, returning its index
+    fn parse_file(&mut self, kind: &str, count: usize) -> Result<usize, TryIntoMoveError> {
+        let expected = format!("{kind} from `a` to `{}`", notation::file_char(count - 1));
+        self.parse(&expected, |c| {
+            ('a'..).take(count).position(|file| file == c)
+        })
+    }
+
+    /// Reads a number from 1 to `count`, taking as many digits as fit, and returns it
+    /// counting from 0
+    fn parse_rank(&mut self, kind: &str, count: usize) -> Result<usize, TryIntoMoveError> {
+        let digit = |c: char| c.to_digit(10).map(|digit| digit as usize);
+        let expected = format!("{kind} from `1` to `{count}`");
+        let mut rank = self.parse(&expected, |c| {
+            digit(c).filter(|&rank| 1 <= rank && rank <= count)
+        })?;
+        while let Some(next) = self.chars.clone().next().and_then(|(_, c)| digit(c)) {
+            if rank * 10 + next > count {
+                break;
+            }
+            rank = rank * 10 + next;
+            self.chars.next();
+        }
+        Ok(rank - 1)
+    }
+
+    fn end(mut self) -> Result<(), TryIntoMoveError> {
+        match self.chars.next() {
+            Some((index, found)) => Err(TryIntoMoveError::UnexpectedChar {
+                index,
+                found,
+                expected: "the end of the move".to_owned(),
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The kind of move a custom move starts with
+enum MoveStart {
+    Pawn(Direction),
+    Fence(Axis),
+}
+
+const fn direction(c: char) -> Option<Direction> {
+    match c {
+        'w' => Some(Direction::Up),
+        'a' => Some(Direction::Left),
+        's' => Some(Direction::Down),
+        'd' => Some(Direction::Right),
+        _ => None,
+    }
+}
+
+/// Parses a move on a board of the standard size
 impl TryFrom<String> for Move {
     type Error = TryIntoMoveError;
 
     fn try_from(value: String) -> Result<Self, TryIntoMoveError> {
-        use Axis::{Horizontal, Vertical};
-        use Direction::{Down, Left, Right, Up};
-        use Move::{MovePlayer, PlaceFence};
-        use TryIntoMoveError::{UnexpectedEndOfString, UnrecognizedChar};
-        let mut chars = value.chars();
-        let Some(first) = chars.next() else {
-            return Err(UnexpectedEndOfString);
-        };
-        let parsed = match first.to_lowercase().next().ok_or(UnrecognizedChar)? {
-            dir @ ('w' | 'a' | 's' | 'd') => {
-                let second_dir = {
-                    match chars.next() {
-                        Some('w') => Some(Up),
-                        Some('a') => Some(Left),
-                        Some('s') => Some(Down),
-                        Some('d') => Some(Right),
-                        None => None,
-                        Some(_) => return Err(UnrecognizedChar),
-                    }
+        Self::parse(&value, board::STANDARD_SIZE)
+    }
+}
+
+impl Move {
+    /// Parses a move on a board of `size`, either in the standard notation or in the custom one
+    /// made of directions (`w`, `a`, `s`, `d`) or a fence axis (`-` or `|`) and slot, counted
+    /// from the top left
+    pub fn parse(value: &str, size: usize) -> Result<Self, TryIntoMoveError> {
+        if value.chars().nth(1).is_some_and(|c| c.is_ascii_digit()) {
+            return notation::parse_move(value, size);
+        }
+        let mut chars = MoveChars::new(value);
+        let start = chars.parse("a move, like `e2`, `e3h`, `w` or `-a1`", |c| match c {
+            '-' | 'h' => Some(MoveStart::Fence(Axis::Horizontal)),
+            '|' | 'v' => Some(MoveStart::Fence(Axis::Vertical)),
+            c => direction(c).map(MoveStart::Pawn),
+        })?;
+        let parsed = match start {
+            MoveStart::Pawn(dir) => {
+                let second_dir = if chars.is_empty() {
+                    None
+                } else {
+                    Some(chars.parse("a direction (`w`, `a`, `s` or `d`)", direction)?)
                 };
-                match dir {
-                    'w' => Ok(MovePlayer(Up, second_dir)),
-                    'a' => Ok(MovePlayer(Left, second_dir)),
-                    's' => Ok(MovePlayer(Down, second_dir)),
-                    'd' => Ok(MovePlayer(Right, second_dir)),
-                    _ => unreachable!(),
-                }
+                Self::MovePlayer(dir, second_dir)
             }
-            axis @ ('-' | 'h' | '|' | 'v') => {
-                let (Some(x_c), Some(y_c)) = (chars.next(), chars.next()) else {
-                    return Err(UnexpectedEndOfString);
-                };
-                let (x, y) = (x_c as usize - 'a' as usize, y_c as usize - '1' as usize);
-                if !(0..9).contains(&x) || !(0..9).contains(&y) {
-                    return Err(UnrecognizedChar);
-                }
-                Ok(PlaceFence(
-                    match axis {
-                        '-' | 'h' => Horizontal,
-                        '|' | 'v' => Vertical,
-                        _ => unreachable!(),
-                    },
-                    (x, y),
-                ))
+            MoveStart::Fence(axis) => {
+                let x = chars.parse_file("a fence column", size - 1)?;
+                let y = chars.parse_rank("a fence row", size - 1)?;
+                Self::PlaceFence(axis, (x, y))
             }
-            _ => Err(UnrecognizedChar),
         };
-        if chars.peekable().peek().is_some() {
-            return Err(UnrecognizedChar);
-        }
-
-        parsed
+        chars.end()?;
+        Ok(parsed)
     }
 }
@@ -1,12 +1,92 @@
+mod bitboard;
+pub mod explanation;
+#[cfg(test)]
+pub mod invariants;
 mod move_generation;
-mod search_and_evaluation;
+mod zobrist;
 
 pub mod fence_move;
+pub mod mcts;
 pub mod pawn_move;
+mod perft;
+pub mod position;
+pub mod render;
+pub mod search_and_evaluation;
+mod shortest_path;
+pub mod transposition_table;
 use core::fmt::Display;
 
 use super::{Axis, Move, PlayerColor};
 
+/// Most fences a player can have, as many as there are fence slots on the standard board, which
+/// the Zobrist keys for fence counts are made for
+pub const MAX_FENCES: u32 = 64;
+/// Size of the board in the standard game, which moves and positions are written for by default
+pub const STANDARD_SIZE: usize = 9;
+/// Largest board whose squares all fit in the wall bitboards
+pub const MAX_SIZE: usize = 11;
+
+/// Turn order of a two-player game
+const TWO_PLAYERS: [PlayerColor; 2] = [PlayerColor::White, PlayerColor::Black];
+/// Turn order of a four-player game, going clockwise around the board
+const FOUR_PLAYERS: [PlayerColor; 4] = [
+    PlayerColor::White,
+    PlayerColor::Red,
+    PlayerColor::Black,
+    PlayerColor::Green,
+];
+
+/// The board a game is played on, with each player starting in the middle of their side
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoardConfig {
+    /// Number of squares along each side, which has to be odd so that the pawns start in the
+    /// middle column
+    pub size: usize,
+    /// Fences each player starts with
+    pub fences: u32,
+    /// Either 2, or 4 for a four-player game
+    pub players: usize,
+}
+
+impl Default for BoardConfig {
+    fn default() -> Self {
+        Self {
+            size: STANDARD_SIZE,
+            fences: Self::standard_fences(2),
+            players: 2,
+        }
+    }
+}
+
+impl BoardConfig {
+    pub const fn is_valid_size(size: usize) -> bool {
+        size % 2 == 1 && 3 <= size && size <= MAX_SIZE
+    }
+
+    pub const fn is_valid_player_count(players: usize) -> bool {
+        matches!(players, 2 | 4)
+    }
+
+    pub const fn is_valid_fence_count(fences: u32) -> bool {
+        fences <= MAX_FENCES
+    }
+
+    /// Fences each player gets in the standard game, where the 20 fences are shared evenly
+    #[allow(clippy::cast_possible_truncation)]
+    pub const fn standard_fences(players: usize) -> u32 {
+        20 / players as u32
+    }
+
+    /// The standard game for `players` players
+    pub const fn with_players(players: usize) -> Self {
+        Self {
+            size: STANDARD_SIZE,
+            fences: Self::standard_fences(players),
+            players,
+        }
+    }
+}
+
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
 enum FenceLegality {
     #[default]
@@ -37,107 +117,196 @@ impl FenceLegality {
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Board {
-    squares: [[Option<PlayerColor>; 9]; 9],
-    fences: [[Option<Axis>; 8]; 8],
-    legal_fence_places: [[FenceLegality; 8]; 8],
-    black_pawn: (usize, usize),
-    white_pawn: (usize, usize),
-    fences_left_for_white: u32,
-    fences_left_for_black: u32,
+    /// Number of squares along each side, the parts of the arrays below past it are unused
+    size: usize,
+    squares: [[Option<PlayerColor>; MAX_SIZE]; MAX_SIZE],
+    fences: [[Option<Axis>; MAX_SIZE - 1]; MAX_SIZE - 1],
+    /// The same fences as `fences`, in the form used for movement and path finding
+    walls: bitboard::Walls,
+    legal_fence_places: [[FenceLegality; MAX_SIZE - 1]; MAX_SIZE - 1],
+    /// The players taking part, in turn order
+    players: &'static [PlayerColor],
+    /// Squares of the pawns, indexed by player, where those of players not taking part are unused
+    pawns: [(usize, usize); 4],
+    /// Fences left for each player, indexed like `pawns`
+    fences_left: [u32; 4],
+    /// Zobrist key of everything above, kept up to date by the unchecked moves
+    hash: u64,
 }
 
 impl Default for Board {
     fn default() -> Self {
-        Self {
-            squares: {
-                let mut board: [[Option<PlayerColor>; 9]; 9] = Default::default();
-                board[0][4] = Some(PlayerColor::Black);
-                board[8][4] = Some(PlayerColor::White);
-                board
-            },
+        Self::new(BoardConfig::default())
+    }
+}
+impl Board {
+    /// The starting position on the board described by `config`, whose size, number of players
+    /// and fence count have to be valid
+    pub fn new(config: BoardConfig) -> Self {
+        assert!(
+            BoardConfig::is_valid_size(config.size),
+            "Invalid board size {}",
+            config.size
+        );
+        assert!(
+            BoardConfig::is_valid_player_count(config.players),
+            "Invalid number of players {}",
+            config.players
+        );
+        assert!(
+            BoardConfig::is_valid_fence_count(config.fences),
+            "Invalid fence count {}",
+            config.fences
+        );
+        let (last, middle) = (config.size - 1, config.size / 2);
+        let mut board = Self {
+            size: config.size,
+            squares: Default::default(),
             fences: Default::default(),
+            walls: bitboard::Walls::new(config.size),
             legal_fence_places: Default::default(),
-            black_pawn: (4, 0),
-            white_pawn: (4, 8),
-            fences_left_for_black: 10,
-            fences_left_for_white: 10,
+            players: if config.players == 4 {
+                &FOUR_PLAYERS
+            } else {
+                &TWO_PLAYERS
+            },
+            pawns: [(middle, last), (middle, 0), (0, middle), (last, middle)],
+            fences_left: [0; 4],
+            hash: 0,
+        };
+        for &player in board.players {
+            let (x, y) = board.pawn_pos(player);
+            board.squares[y][x] = Some(player);
+            board.fences_left[player as usize] = config.fences;
         }
+        board.hash = board.compute_hash();
+        board
     }
-}
-impl Display for Board {
-    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
-        writeln!(f, "   ╭───┬───┬───┬───┬───┬───┬───┬───┬───╮")?;
-        for row_idx in 0..9 {
-            write!(f, "   │")?;
-            self.squares[row_idx]
-                .iter()
-                .enumerate()
-                .try_for_each(|(idx, sq)| {
-                    let square_fmt = sq.as_ref().map_or("   ", |c| match c {
-                        PlayerColor::White => " W ",
-                        PlayerColor::Black => " B ",
-                    });
-                    let bar_fmt = if idx != 8
-                        && (row_idx < 8 && self.fences[row_idx][idx] == Some(Axis::Vertical)
-                            || row_idx > 0 && self.fences[row_idx - 1][idx] == Some(Axis::Vertical))
-                    {
-                        "\x1b[31m┃\x1b[0m"
-                    } else {
-                        "│"
-                    };
-                    write!(f, "{square_fmt}{bar_fmt}")
-                })?;
-            writeln!(f)?;
-            if row_idx == 8 {
-                break;
+
+    /// The starting position, but with each player having `fences` fences
+    pub fn with_fences(fences: u32) -> Self {
+        Self::new(BoardConfig {
+            fences,
+            ..BoardConfig::default()
+        })
+    }
+
+    /// Number of squares along each side
+    pub const fn size(&self) -> usize {
+        self.size
+    }
+
+    /// The players taking part, in turn order starting with White
+    pub const fn players(&self) -> &'static [PlayerColor] {
+        self.players
+    }
+
+    /// Where `player` comes in the turn order, White being first
+    pub const fn turn_index(&self, player: PlayerColor) -> usize {
+        let mut idx = 0;
+        while idx < self.players.len() {
+            if self.players[idx] as usize == player as usize {
+                return idx;
             }
-            write!(f, " {}─┼", row_idx + 1)?;
-            (0..9).try_for_each(|idx| {
-                let square_side_fmt =
-                    if idx < 8 && self.fences[row_idx][idx] == Some(Axis::Horizontal) {
-                        "\x1b[31m━━━"
-                    } else if idx > 0 && self.fences[row_idx][idx - 1] == Some(Axis::Horizontal) {
-                        "━━━\x1b[0m"
-                    } else {
-                        "───"
-                    };
-                let square_corner_fmt = if idx == 8 {
-                    "┤"
-                } else {
-                    match self.fences[row_idx][idx] {
-                        Some(Axis::Vertical) => "\x1b[31m┃\x1b[0m",
-                        Some(Axis::Horizontal) => "━",
-                        _ => "┼",
-                    }
-                };
-                write!(f, "{square_side_fmt}{square_corner_fmt}",)
-            })?;
-            writeln!(f)?;
+            idx += 1;
         }
-        writeln!(f, "   ╰───┼───┼───┼───┼───┼───┼───┼───┼───╯")?;
-        writeln!(f, "       A   B   C   D   E   F   G   H    ")?;
-        writeln!(f)?;
-        writeln!(
-            f,
-            "   White - {:>2} fences │ Black - {:>2} fences",
-            self.fences_left_for_white, self.fences_left_for_black
-        )?;
-        Ok(())
+        panic!("The player isn't taking part in the game")
+    }
+
+    /// The player whose turn comes after `player`'s
+    pub const fn next_player(&self, player: PlayerColor) -> PlayerColor {
+        self.players[(self.turn_index(player) + 1) % self.players.len()]
+    }
+
+    /// The player whose turn came before `player`'s
+    pub const fn previous_player(&self, player: PlayerColor) -> PlayerColor {
+        let count = self.players.len();
+        self.players[(self.turn_index(player) + count - 1) % count]
+    }
+
+    /// Whether `player` has any legal move. In four-player games a pawn can be boxed in by the
+    /// other pawns and the fences, which leaves a player without fences unable to move.
+    pub fn can_move(&self, player: PlayerColor) -> bool {
+        !self.legal_pawn_moves(player).is_empty() || !self.legal_fence_moves(player).is_empty()
+    }
+
+    /// `player`, or the first player after them in turn who can move, as players who can't
+    /// move pass. Stays with `player` if nobody can move.
+    pub fn first_able_to_move(&self, player: PlayerColor) -> PlayerColor {
+        let mut candidate = player;
+        for _ in 0..self.players.len() {
+            if self.can_move(candidate) {
+                return candidate;
+            }
+            candidate = self.next_player(candidate);
+        }
+        player
+    }
+
+    /// The player who moves after `player`, skipping those who can't move
+    pub fn next_player_to_move(&self, player: PlayerColor) -> PlayerColor {
+        self.first_able_to_move(self.next_player(player))
     }
 }
 impl Board {
     pub const fn is_game_won(&self) -> Option<PlayerColor> {
-        match (self.white_pawn.1, self.black_pawn.1) {
-            (0, _) => Some(PlayerColor::White),
-            (_, 8) => Some(PlayerColor::Black),
-            _ => None,
+        let mut idx = 0;
+        while idx < self.players.len() {
+            let player = self.players[idx];
+            if self.is_goal_square(player, self.pawn_pos(player)) {
+                return Some(player);
+            }
+            idx += 1;
         }
+        None
+    }
+
+    pub const fn pawn_pos(&self, player: PlayerColor) -> (usize, usize) {
+        self.pawns[player as usize]
+    }
+
+    /// A key identifying the position with `to_move` to move, for use in hash tables
+    pub const fn hash_key(&self, to_move: PlayerColor) -> u64 {
+        self.hash ^ zobrist::KEYS.to_move(to_move)
     }
 
-    const fn pawn_pos(&self, player: PlayerColor) -> (usize, usize) {
+    fn compute_hash(&self) -> u64 {
+        let mut hash = 0;
+        for &player in self.players {
+            hash ^= zobrist::KEYS.pawn(player, self.pawn_pos(player))
+                ^ zobrist::KEYS.fences_left(player, self.fences_left(player));
+        }
+        for (y, row) in self.fences.iter().enumerate() {
+            for (x, fence) in row.iter().enumerate() {
+                if let Some(axis) = fence {
+                    hash ^= zobrist::KEYS.fence(*axis, (x, y));
+                }
+            }
+        }
+        hash
+    }
+
+    pub const fn fences_left(&self, player: PlayerColor) -> u32 {
+        self.fences_left[player as usize]
+    }
+
+    /// The pawn on the square at `(x, y)`, if there is one
+    pub const fn pawn_at(&self, (x, y): (usize, usize)) -> Option<PlayerColor> {
+        self.squares[y][x]
+    }
+
+    /// The fence centered in the slot at `(x, y)`, if one was placed there
+    pub const fn fence_at(&self, (x, y): (usize, usize)) -> Option<Axis> {
+        self.fences[y][x]
+    }
+
+    /// Whether `(x, y)` is on the edge of the board across from where `player` started
+    const fn is_goal_square(&self, player: PlayerColor, (x, y): (usize, usize)) -> bool {
         match player {
-            PlayerColor::White => self.white_pawn,
-            PlayerColor::Black => self.black_pawn,
+            PlayerColor::White => y == 0,
+            PlayerColor::Black => y == self.size - 1,
+            PlayerColor::Red => x == self.size - 1,
+            PlayerColor::Green => x == 0,
         }
     }
 }
@@ -146,8 +315,22 @@ impl Board {
 pub enum MoveMakeFail {
     AddFenceMove(fence_move::Fail),
     PawnMoveFail(pawn_move::Fail),
+    /// The square or fence slot the move refers to isn't on the board
+    OutOfBounds,
+}
+
+impl Display for MoveMakeFail {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::AddFenceMove(fail) => write!(f, "{fail}"),
+            Self::PawnMoveFail(fail) => write!(f, "{fail}"),
+            Self::OutOfBounds => write!(f, "the move is off the board"),
+        }
+    }
 }
 
+impl std::error::Error for MoveMakeFail {}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Lmi {
     // LegalMove inner workings
@@ -155,25 +338,46 @@ enum Lmi {
     PlaceFence(Axis, (usize, usize)),
 }
 
+/// A move checked to be legal, along with the size of the board it was checked on, which is
+/// needed to write it down
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct LegalMove(Lmi);
+pub struct LegalMove(Lmi, usize);
+
+impl LegalMove {
+    /// Where the pawn ends up, if this is a pawn move
+    pub const fn destination(self) -> Option<(usize, usize)> {
+        match self.0 {
+            Lmi::MovePlayer(_, pos) => Some(pos),
+            Lmi::PlaceFence(..) => None,
+        }
+    }
+
+    /// Size of the board the move was made legal on
+    pub const fn board_size(self) -> usize {
+        self.1
+    }
+
+    /// The placed fence, if this is a fence move
+    pub const fn fence(self) -> Option<(Axis, (usize, usize))> {
+        match self.0 {
+            Lmi::MovePlayer(..) => None,
+            Lmi::PlaceFence(axis, pos) => Some((axis, pos)),
+        }
+    }
+}
 
 impl Board {
     pub fn make_legal_move(&mut self, r#move: LegalMove, player: PlayerColor) {
-        match r#move {
-            LegalMove(Lmi::MovePlayer(orig_pos, pos)) => {
-                self.move_pawn_unchecked(player, orig_pos, pos)
-            }
-            LegalMove(Lmi::PlaceFence(axis, pos)) => self.move_fence_unchecked(player, axis, pos),
+        match r#move.0 {
+            Lmi::MovePlayer(orig_pos, pos) => self.move_pawn_unchecked(player, orig_pos, pos),
+            Lmi::PlaceFence(axis, pos) => self.move_fence_unchecked(player, axis, pos),
         }
     }
 
-    pub fn unmake_legal_move(&mut self, r#move: LegalMove, player: PlayerColor) {
-        match r#move {
-            LegalMove(Lmi::MovePlayer(orig_pos, pos)) => {
-                self.unmove_pawn_unchecked(player, orig_pos, pos)
-            }
-            LegalMove(Lmi::PlaceFence(axis, pos)) => self.unmove_fence_unchecked(player, axis, pos),
+    pub const fn unmake_legal_move(&mut self, r#move: LegalMove, player: PlayerColor) {
+        match r#move.0 {
+            Lmi::MovePlayer(orig_pos, pos) => self.unmove_pawn_unchecked(player, orig_pos, pos),
+            Lmi::PlaceFence(axis, pos) => self.unmove_fence_unchecked(player, axis, pos),
         }
     }
 
@@ -182,12 +386,28 @@ impl Board {
         r#move: Move,
         player: PlayerColor,
     ) -> Result<LegalMove, MoveMakeFail> {
-        Ok(LegalMove(match r#move {
+        let size = self.size;
+        Ok(self.legal(match r#move {
             Move::MovePlayer(dir, second_dir) => Lmi::MovePlayer(
                 self.pawn_pos(player),
                 self.pawn_move_destination(player, dir, second_dir)
                     .map_err(MoveMakeFail::PawnMoveFail)?,
             ),
+            Move::PawnTo((x, y)) if x >= size || y >= size => {
+                return Err(MoveMakeFail::OutOfBounds)
+            }
+            Move::PlaceFence(_, (x, y)) if x >= size - 1 || y >= size - 1 => {
+                return Err(MoveMakeFail::OutOfBounds)
+            }
+            Move::PawnTo(pos) => {
+                let r#move = self.legal(Lmi::MovePlayer(self.pawn_pos(player), pos));
+                if !self.legal_pawn_moves(player).contains(&r#move) {
+                    return Err(MoveMakeFail::PawnMoveFail(
+                        pawn_move::Fail::UnreachableSquare,
+                    ));
+                }
+                r#move.0
+            }
             Move::PlaceFence(axis, pos) => {
                 self.is_fence_move_legal(player, axis, pos)
                     .map_err(MoveMakeFail::AddFenceMove)?;
@@ -195,6 +415,10 @@ impl Board {
             }
         }))
     }
+
+    const fn legal(&self, inner: Lmi) -> LegalMove {
+        LegalMove(inner, self.size)
+    }
 }
 
 const fn is_nicely_send<T: Sized + Send + Sync + Unpin>() {}
@@ -202,3 +426,19 @@ const fn is_nicely_send<T: Sized + Send + Sync + Unpin>() {}
 const fn normal_types() {
     is_nicely_send::<Board>();
 }
+
+#[test]
+fn off_board_moves_are_rejected() {
+    let board = Board::default();
+    for r#move in [
+        Move::PawnTo((9, 0)),
+        Move::PawnTo((4, 9)),
+        Move::PlaceFence(Axis::Horizontal, (8, 0)),
+        Move::PlaceFence(Axis::Vertical, (0, 8)),
+    ] {
+        assert_eq!(
+            board.make_move_legal(r#move, PlayerColor::White),
+            Err(MoveMakeFail::OutOfBounds)
+        );
+    }
+}
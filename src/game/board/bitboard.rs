@@ -0,0 +1,232 @@
+//! Walls as bitboards, where bit `STRIDE * y + x` stands for the square `(x, y)`
+//!
+//! Reachability is then a flood fill, moving the whole set of reached squares one step in each
+//! direction at a time with shifts and masks.
+//!
+//! Rows always take up `STRIDE` bits so that the largest board fits, smaller boards leave the
+//! bits past their last column and row unused. Since the edges of the board are walls, the flood
+//! fill never gets to them.
+use super::super::{Axis, Direction, PlayerColor};
+use super::{Board, MAX_SIZE};
+
+pub(super) const STRIDE: usize = MAX_SIZE;
+pub(super) const SQUARES: u128 = (1 << (STRIDE * STRIDE)) - 1;
+const TOP_ROW: u128 = (1 << STRIDE) - 1;
+const LEFT_COLUMN: u128 = column(0);
+
+pub(super) const fn bit((x, y): (usize, usize)) -> u128 {
+    1 << (STRIDE * y + x)
+}
+
+pub(super) const fn row(y: usize) -> u128 {
+    TOP_ROW << (STRIDE * y)
+}
+
+const fn column(x: usize) -> u128 {
+    let mut column = 0;
+    let mut y = 0;
+    while y < STRIDE {
+        column |= bit((x, y));
+        y += 1;
+    }
+    column
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Walls {
+    /// Squares from which moving right is blocked, by a fence or by the edge of the board
+    blocked_right: u128,
+    /// Squares from which moving down is blocked, by a fence or by the edge of the board
+    blocked_down: u128,
+}
+
+impl Walls {
+    /// The walls of an empty board with `size` squares along each side, which are its edges
+    pub(super) const fn new(size: usize) -> Self {
+        Self {
+            blocked_right: column(size - 1),
+            blocked_down: row(size - 1),
+        }
+    }
+
+    /// Adds the fence, or removes it if it is already there
+    pub(super) const fn toggle_fence(self, axis: Axis, (x, y): (usize, usize)) -> Self {
+        match axis {
+            Axis::Horizontal => Self {
+                blocked_down: self.blocked_down ^ (bit((x, y)) | bit((x + 1, y))),
+                ..self
+            },
+            Axis::Vertical => Self {
+                blocked_right: self.blocked_right ^ (bit((x, y)) | bit((x, y + 1))),
+                ..self
+            },
+        }
+    }
+
+    pub(super) const fn is_obstructed(self, (x, y): (usize, usize), dir: Direction) -> bool {
+        match dir {
+            Direction::Right => self.blocked_right & bit((x, y)) != 0,
+            Direction::Down => self.blocked_down & bit((x, y)) != 0,
+            Direction::Left => x == 0 || self.blocked_right & bit((x - 1, y)) != 0,
+            Direction::Up => y == 0 || self.blocked_down & bit((x, y - 1)) != 0,
+        }
+    }
+
+    pub(super) const fn is_blocked_right(self, square: usize) -> bool {
+        self.blocked_right & (1 << square) != 0
+    }
+
+    pub(super) const fn is_blocked_down(self, square: usize) -> bool {
+        self.blocked_down & (1 << square) != 0
+    }
+
+    /// The squares reachable from `reach` in at most one step
+    pub(super) const fn expand(self, reach: u128) -> u128 {
+        let right = (reach & !self.blocked_right) << 1;
+        let left = ((reach & !LEFT_COLUMN) >> 1) & !self.blocked_right;
+        let down = (reach & !self.blocked_down) << STRIDE;
+        let up = (reach >> STRIDE) & !self.blocked_down;
+        (reach | right | left | down | up) & SQUARES
+    }
+
+    pub(super) const fn can_reach(self, from: u128, goal: u128) -> bool {
+        self.distance(from, goal).is_some()
+    }
+
+    /// Number of steps needed to get from any of the squares in `from` to any of those in `goal`
+    pub(super) const fn distance(self, from: u128, goal: u128) -> Option<u32> {
+        let mut reach = from;
+        let mut distance = 0;
+        while reach & goal == 0 {
+            let next = self.expand(reach);
+            if next == reach {
+                return None;
+            }
+            reach = next;
+            distance += 1;
+        }
+        Some(distance)
+    }
+}
+
+impl Board {
+    pub(super) const fn can_pawn_reach_goal(&self, walls: Walls, player: PlayerColor) -> bool {
+        walls.can_reach(bit(self.pawn_pos(player)), self.goal(player))
+    }
+
+    /// The squares `player` is trying to reach, the edge across from where they started
+    pub(super) const fn goal(&self, player: PlayerColor) -> u128 {
+        let last = self.size - 1;
+        match player {
+            PlayerColor::White => row(0),
+            PlayerColor::Black => row(last),
+            PlayerColor::Red => column(last),
+            PlayerColor::Green => column(0),
+        }
+    }
+
+    /// The check `is_fence_move_legal` did before walls were bitboards, kept as a reference
+    /// for tests and benchmarks
+    pub fn is_fence_legal_by_dfs(
+        &self,
+        player: PlayerColor,
+        axis: Axis,
+        pos: (usize, usize),
+    ) -> bool {
+        if self.fences_left(player) == 0 || !self.legal_fence_places[pos.1][pos.0].does_allow(axis)
+        {
+            return false;
+        }
+        let mut clone = self.clone();
+        clone.fences[pos.1][pos.0] = Some(axis);
+        clone.are_pawns_able_to_win_by_dfs()
+    }
+
+    fn are_pawns_able_to_win_by_dfs(&self) -> bool {
+        let dfs = |player| {
+            let pawn = self.pawn_pos(player);
+            let mut stack = vec![pawn];
+            let mut is_on_stack = [[false; MAX_SIZE]; MAX_SIZE];
+            is_on_stack[pawn.1][pawn.0] = true;
+            while let Some((x, y)) = stack.pop() {
+                if self.is_goal_square(player, (x, y)) {
+                    return true;
+                }
+                for dir in [
+                    Direction::Down,
+                    Direction::Left,
+                    Direction::Right,
+                    Direction::Up,
+                ] {
+                    if self.is_obstructed_by_fences((x, y), dir) {
+                        continue;
+                    }
+                    let (x1, y1) = dir.offset((x, y));
+                    if is_on_stack[y1][x1] {
+                        continue;
+                    }
+                    stack.push((x1, y1));
+                    is_on_stack[y1][x1] = true;
+                }
+            }
+            false
+        };
+        self.players.iter().all(|&player| dfs(player))
+    }
+
+    fn is_obstructed_by_fences(&self, (x, y): (usize, usize), dir: Direction) -> bool {
+        let last = self.size - 1;
+        match dir {
+            Direction::Left => {
+                x == 0
+                    || y < last && self.fences[y][x - 1] == Some(Axis::Vertical)
+                    || y > 0 && self.fences[y - 1][x - 1] == Some(Axis::Vertical)
+            }
+            Direction::Right => {
+                x == last
+                    || y < last && self.fences[y][x] == Some(Axis::Vertical)
+                    || y > 0 && self.fences[y - 1][x] == Some(Axis::Vertical)
+            }
+            Direction::Down => {
+                y == last
+                    || x < last && self.fences[y][x] == Some(Axis::Horizontal)
+                    || x > 0 && self.fences[y][x - 1] == Some(Axis::Horizontal)
+            }
+            Direction::Up => {
+                y == 0
+                    || x < last && self.fences[y - 1][x] == Some(Axis::Horizontal)
+                    || x > 0 && self.fences[y - 1][x - 1] == Some(Axis::Horizontal)
+            }
+        }
+    }
+}
+
+#[test]
+fn agrees_with_the_fence_array() {
+    super::invariants::for_random_positions(|board, turn, _| {
+        let size = board.size;
+        for y in 0..size {
+            for x in 0..size {
+                for dir in [
+                    Direction::Down,
+                    Direction::Left,
+                    Direction::Right,
+                    Direction::Up,
+                ] {
+                    assert_eq!(
+                        board.is_obstructed((x, y), dir),
+                        board.is_obstructed_by_fences((x, y), dir)
+                    );
+                }
+                for axis in [Axis::Horizontal, Axis::Vertical] {
+                    if x < size - 1 && y < size - 1 {
+                        assert_eq!(
+                            board.is_fence_move_legal(turn, axis, (x, y)).is_ok(),
+                            board.is_fence_legal_by_dfs(turn, axis, (x, y))
+                        );
+                    }
+                }
+            }
+        }
+    });
+}
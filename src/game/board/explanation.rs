@@ -0,0 +1,150 @@
+//! Explanations of why a move can't be made, so that front-ends don't have to turn every failure
+//! into a message themselves
+use core::fmt::Display;
+
+use super::super::{notation, Axis, Move, PlayerColor};
+use super::{fence_move, Board, LegalMove, MoveMakeFail};
+
+/// A move that can't be made, along with what a player would want to know about it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IllegalMove {
+    pub r#move: Move,
+    pub reason: MoveMakeFail,
+    /// The placed fence that the attempted one overlaps or crosses
+    pub colliding_fence: Option<(Axis, (usize, usize))>,
+    /// The pawns that the attempted fence would cut off from their goal
+    pub cut_off: Vec<PlayerColor>,
+    /// The squares the pawn can move to instead, when the move was a pawn move
+    pub alternatives: Vec<(usize, usize)>,
+    /// Size of the board, which the squares and fences are named for
+    pub board_size: usize,
+}
+
+impl Display for IllegalMove {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.reason)?;
+        if let Some((axis, pos)) = self.colliding_fence {
+            write!(f, " ({})", notation::fence_name(axis, pos, self.board_size))?;
+        }
+        if !self.cut_off.is_empty() {
+            let pawns: Vec<_> = self.cut_off.iter().map(ToString::to_string).collect();
+            write!(f, " ({})", pawns.join(" and "))?;
+        }
+        if !self.alternatives.is_empty() {
+            let squares: Vec<_> = self
+                .alternatives
+                .iter()
+                .map(|&pos| notation::square_name(pos, self.board_size))
+                .collect();
+            write!(f, ", the pawn can move to {}", squares.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for IllegalMove {}
+
+impl Board {
+    /// Same as `make_move_legal`, but explains what is wrong with the move when it fails
+    pub fn explain_move(
+        &self,
+        r#move: Move,
+        player: PlayerColor,
+    ) -> Result<LegalMove, IllegalMove> {
+        let reason = match self.make_move_legal(r#move, player) {
+            Ok(legal_move) => return Ok(legal_move),
+            Err(reason) => reason,
+        };
+        let mut explanation = IllegalMove {
+            r#move,
+            reason,
+            colliding_fence: None,
+            cut_off: vec![],
+            alternatives: vec![],
+            board_size: self.size,
+        };
+        match (r#move, reason) {
+            (Move::MovePlayer(..) | Move::PawnTo(_), _) => {
+                explanation.alternatives = self
+                    .legal_pawn_moves(player)
+                    .into_iter()
+                    .filter_map(LegalMove::destination)
+                    .collect();
+            }
+            (
+                Move::PlaceFence(axis, pos),
+                MoveMakeFail::AddFenceMove(fence_move::Fail::Collides),
+            ) => {
+                explanation.colliding_fence = self.colliding_fence(axis, pos);
+            }
+            (
+                Move::PlaceFence(axis, pos),
+                MoveMakeFail::AddFenceMove(fence_move::Fail::NoPathRemaining),
+            ) => {
+                let walls = self.walls.toggle_fence(axis, pos);
+                explanation.cut_off = self
+                    .players
+                    .iter()
+                    .copied()
+                    .filter(|&pawn| !self.can_pawn_reach_goal(walls, pawn))
+                    .collect();
+            }
+            (Move::PlaceFence(..), _) => {}
+        }
+        Err(explanation)
+    }
+
+    /// A placed fence that overlaps or crosses a fence at `(x, y)`
+    fn colliding_fence(
+        &self,
+        axis: Axis,
+        (x, y): (usize, usize),
+    ) -> Option<(Axis, (usize, usize))> {
+        if let Some(placed) = self.fences[y][x] {
+            return Some((placed, (x, y)));
+        }
+        let neighbours = match axis {
+            Axis::Horizontal => [x.checked_sub(1).map(|x| (x, y)), Some((x + 1, y))],
+            Axis::Vertical => [y.checked_sub(1).map(|y| (x, y)), Some((x, y + 1))],
+        };
+        neighbours
+            .into_iter()
+            .flatten()
+            .filter(|&(x, y)| x + 1 < self.size && y + 1 < self.size)
+            .find(|&(x, y)| self.fences[y][x] == Some(axis))
+            .map(|pos| (axis, pos))
+    }
+}
+
+#[test]
+fn explains_illegal_moves() {
+    let (board, player) =
+        Board::from_position_str("a1 e9 a1v,c2h 10 10 w").expect("The position is valid");
+
+    let collides = board
+        .explain_move(Move::PlaceFence(Axis::Horizontal, (3, 6)), player)
+        .expect_err("d2h overlaps c2h");
+    assert_eq!(collides.colliding_fence, Some((Axis::Horizontal, (2, 6))));
+    assert_eq!(
+        collides.to_string(),
+        "the fence would collide with another fence (c2h)"
+    );
+
+    let cut_off = board
+        .explain_move(Move::PlaceFence(Axis::Horizontal, (0, 6)), player)
+        .expect_err("a2h closes White in");
+    assert_eq!(cut_off.cut_off, vec![PlayerColor::White]);
+
+    let blocked = board
+        .explain_move(Move::PawnTo((1, 8)), player)
+        .expect_err("a1v is in the way");
+    assert_eq!(blocked.alternatives, vec![(0, 7)]);
+    assert_eq!(
+        blocked.to_string(),
+        "the pawn can't reach that square in one move, the pawn can move to a2"
+    );
+
+    assert!(board
+        .explain_move(Move::PlaceFence(Axis::Vertical, (4, 4)), player)
+        .is_ok());
+}
@@ -1,11 +1,28 @@
-use super::super::{Axis, Direction, PlayerColor};
-use super::{Board, FenceLegality};
+use core::fmt::Display;
+
+use super::super::{Axis, PlayerColor};
+use super::{bitboard::Walls, zobrist, Board, FenceLegality};
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Fail {
     Collides,
     NoPathRemaining,
     NoFencesRemaining,
+    OutOfBounds,
+}
+
+impl Display for Fail {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Collides => write!(f, "the fence would collide with another fence"),
+            Self::NoPathRemaining => {
+                write!(f, "the fence would leave a pawn with no path to its goal")
+            }
+            Self::NoFencesRemaining => write!(f, "there are no fences left"),
+            Self::OutOfBounds => write!(f, "there is no such fence slot on the board"),
+        }
+    }
 }
+
 impl Board {
     pub(super) fn move_fence_unchecked(
         &mut self,
@@ -13,11 +30,17 @@ impl Board {
         axis: Axis,
         (x, y): (usize, usize),
     ) {
+        self.hash ^= zobrist::KEYS.fences_left(player, self.fences_left(player));
+        self.fences_left[player as usize] -= 1;
+        self.hash ^= zobrist::KEYS.fences_left(player, self.fences_left(player));
+        self.place_fence(axis, (x, y));
+    }
+
+    /// Puts the fence on the board, without taking it from anyone
+    pub(super) fn place_fence(&mut self, axis: Axis, (x, y): (usize, usize)) {
         self.fences[y][x] = Some(axis);
-        match player {
-            PlayerColor::White => self.fences_left_for_white -= 1,
-            PlayerColor::Black => self.fences_left_for_black -= 1,
-        };
+        self.walls = self.walls.toggle_fence(axis, (x, y));
+        self.hash ^= zobrist::KEYS.fence(axis, (x, y));
 
         self.legal_fence_places[y][x] = FenceLegality::None;
         {
@@ -29,7 +52,7 @@ impl Board {
                     if x != 0 {
                         change(x - 1, y);
                     }
-                    if x != 7 {
+                    if x + 2 != self.size {
                         change(x + 1, y);
                     }
                 }
@@ -37,25 +60,26 @@ impl Board {
                     if y != 0 {
                         change(x, y - 1);
                     }
-                    if y != 7 {
+                    if y + 2 != self.size {
                         change(x, y + 1);
                     }
                 }
-            };
+            }
         }
     }
 
-    pub(super) fn unmove_fence_unchecked(
+    pub(super) const fn unmove_fence_unchecked(
         &mut self,
         player: PlayerColor,
         axis: Axis,
         (x, y): (usize, usize),
     ) {
         self.fences[y][x] = None;
-        match player {
-            PlayerColor::White => self.fences_left_for_white += 1,
-            PlayerColor::Black => self.fences_left_for_black += 1,
-        };
+        self.walls = self.walls.toggle_fence(axis, (x, y));
+        self.hash ^= zobrist::KEYS.fence(axis, (x, y))
+            ^ zobrist::KEYS.fences_left(player, self.fences_left(player));
+        self.fences_left[player as usize] += 1;
+        self.hash ^= zobrist::KEYS.fences_left(player, self.fences_left(player));
 
         self.figure_correct_legality_at((x, y));
         match axis {
@@ -63,7 +87,7 @@ impl Board {
                 if x != 0 {
                     self.figure_correct_legality_at((x - 1, y));
                 }
-                if x != 7 {
+                if x + 2 != self.size {
                     self.figure_correct_legality_at((x + 1, y));
                 }
             }
@@ -71,52 +95,70 @@ impl Board {
                 if y != 0 {
                     self.figure_correct_legality_at((x, y - 1));
                 }
-                if y != 7 {
+                if y + 2 != self.size {
                     self.figure_correct_legality_at((x, y + 1));
                 }
             }
-        };
+        }
     }
 
-    fn figure_correct_legality_at(&mut self, (x, y): (usize, usize)) {
+    const fn figure_correct_legality_at(&mut self, (x, y): (usize, usize)) {
+        if self.fences[y][x].is_some() {
+            self.legal_fence_places[y][x] = FenceLegality::None;
+            return;
+        }
         let mut legality = FenceLegality::Any;
 
-        if x > 0 {
-            if let Some(f) = self.fences[y][x - 1] {
-                legality = legality.restrict(f);
-            };
+        // Only a horizontal fence beside the slot or a vertical one above or below it overlaps
+        // a fence placed here, as in `place_fence`
+        if x > 0 && matches!(self.fences[y][x - 1], Some(Axis::Horizontal)) {
+            legality = legality.restrict(Axis::Horizontal);
         }
-        if y > 0 {
-            if let Some(f) = self.fences[y - 1][x] {
-                legality = legality.restrict(f);
-            };
+        if x + 2 < self.size && matches!(self.fences[y][x + 1], Some(Axis::Horizontal)) {
+            legality = legality.restrict(Axis::Horizontal);
         }
-        if x < 7 {
-            if let Some(f) = self.fences[y][x + 1] {
-                legality = legality.restrict(f);
-            };
+        if y > 0 && matches!(self.fences[y - 1][x], Some(Axis::Vertical)) {
+            legality = legality.restrict(Axis::Vertical);
         }
-        if y < 7 {
-            if let Some(f) = self.fences[y + 1][x] {
-                legality = legality.restrict(f);
-            };
+        if y + 2 < self.size && matches!(self.fences[y + 1][x], Some(Axis::Vertical)) {
+            legality = legality.restrict(Axis::Vertical);
         }
         self.legal_fence_places[y][x] = legality;
     }
 
-    pub fn is_fence_move_legal(
+    pub const fn is_fence_move_legal(
+        &self,
+        player: PlayerColor,
+        axis: Axis,
+        (x, y): (usize, usize),
+    ) -> Result<(), Fail> {
+        use Fail::NoPathRemaining;
+
+        if let Err(fail) = self.can_fence_be_placed(player, axis, (x, y)) {
+            return Err(fail);
+        }
+
+        if self.can_every_pawn_reach_goal(self.walls.toggle_fence(axis, (x, y))) {
+            Ok(())
+        } else {
+            Err(NoPathRemaining)
+        }
+    }
+
+    /// Checks everything about placing the fence except whether the pawns can still win
+    pub(super) const fn can_fence_be_placed(
         &self,
         player: PlayerColor,
         axis: Axis,
         (x, y): (usize, usize),
     ) -> Result<(), Fail> {
         use Axis::{Horizontal, Vertical};
-        use Fail::{Collides, NoFencesRemaining, NoPathRemaining};
+        use Fail::{Collides, NoFencesRemaining, OutOfBounds};
 
-        if 0 == match player {
-            PlayerColor::White => self.fences_left_for_white,
-            PlayerColor::Black => self.fences_left_for_black,
-        } {
+        if x >= self.size - 1 || y >= self.size - 1 {
+            return Err(OutOfBounds);
+        }
+        if self.fences_left(player) == 0 {
             return Err(NoFencesRemaining);
         }
 
@@ -126,59 +168,37 @@ impl Board {
         {
             return Err(Collides);
         }
+        Ok(())
+    }
 
-        let mut clone = self.clone();
-        clone.fences[y][x] = Some(axis);
-        let are_pawns_able_to_win = clone.are_pawns_able_to_win();
+    pub const fn are_pawns_able_to_win(&self) -> bool {
+        self.can_every_pawn_reach_goal(self.walls)
+    }
 
-        if are_pawns_able_to_win {
-            Ok(())
-        } else {
-            Err(NoPathRemaining)
+    const fn can_every_pawn_reach_goal(&self, walls: Walls) -> bool {
+        let mut idx = 0;
+        while idx < self.players.len() {
+            if !self.can_pawn_reach_goal(walls, self.players[idx]) {
+                return false;
+            }
+            idx += 1;
         }
+        true
     }
+}
 
-    pub fn are_pawns_able_to_win(&self) -> bool {
-        let dfs = |pawn: (usize, usize), yl, dirs| {
-            let mut stack = vec![pawn];
-            let mut is_on_stack = [[false; 9]; 9];
-            is_on_stack[pawn.1][pawn.0] = true;
-            while let Some((x, y)) = stack.pop() {
-                if y == yl {
-                    return true;
-                }
-                for dir in dirs {
-                    if self.is_obstructed((x, y), dir) {
-                        continue;
-                    }
-                    let (x1, y1) = dir.offset((x, y));
-                    if is_on_stack[y1][x1] {
-                        continue;
-                    }
-                    stack.push((x1, y1));
-                    is_on_stack[y1][x1] = true;
-                }
+#[test]
+fn fences_outside_the_board_are_rejected() {
+    for position in ["e1 e9 - 10 10 w", "5x5 c1 c5 - 3 3 w"] {
+        let (board, player) = Board::from_position_str(position).expect("The position is valid");
+        let last = board.size() - 1;
+        for axis in [Axis::Horizontal, Axis::Vertical] {
+            for pos in [(last, 0), (0, last)] {
+                assert_eq!(
+                    board.is_fence_move_legal(player, axis, pos),
+                    Err(Fail::OutOfBounds)
+                );
             }
-            false
-        };
-        dfs(
-            self.white_pawn,
-            0,
-            [
-                Direction::Down,
-                Direction::Left,
-                Direction::Right,
-                Direction::Up,
-            ],
-        ) && dfs(
-            self.black_pawn,
-            8,
-            [
-                Direction::Down,
-                Direction::Left,
-                Direction::Right,
-                Direction::Up,
-            ],
-        )
+        }
     }
 }
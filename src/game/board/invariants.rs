@@ -0,0 +1,101 @@
+//! Rules invariants checked over many random games, and positions for tests elsewhere
+use rand::{rngs::StdRng, SeedableRng};
+
+use super::{Board, BoardConfig, LegalMove};
+use crate::game::{Move, PlayerColor};
+
+const GAMES: u64 = 40;
+/// Long random games are mostly pawns wandering around once the fences run out
+const MAX_PLIES: usize = 150;
+
+/// A four-player position where White is fenced in from above, with Green and Red in a row
+/// beside it and no fences left, so that it can't move even though it has a path to its goal
+pub fn boxed_in_white() -> Board {
+    let (board, _) =
+        Board::from_position_str("a1 e9 c1 b1 a1h 0 5 5 5 w").expect("The position is valid");
+    board
+}
+
+/// Plays `GAMES` random games on boards of several sizes with both player counts, calling `check`
+/// on every position reached along with the side to move and the fences each player started with
+pub(super) fn for_random_positions(mut check: impl FnMut(&Board, PlayerColor, u32)) {
+    for seed in 0..GAMES {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let fences = [10, 3, 0][usize::try_from(seed % 3).expect("Small numbers fit")];
+        let size = [9, 5, 7, 11][usize::try_from(seed % 4).expect("Small numbers fit")];
+        let players = [2, 4][usize::try_from(seed / 4 % 2).expect("Small numbers fit")];
+        let board = Board::new(BoardConfig {
+            size,
+            fences,
+            players,
+        });
+        board.play_random_game(PlayerColor::White, MAX_PLIES, &mut rng, |board, turn| {
+            check(board, turn, fences);
+        });
+    }
+}
+
+#[test]
+fn unmaking_restores_the_board() {
+    for_random_positions(|board, turn, _| {
+        let mut after = board.clone();
+        for r#move in board.legal_moves(turn) {
+            after.make_legal_move(r#move, turn);
+            assert_eq!(after.hash, after.compute_hash());
+            after.unmake_legal_move(r#move, turn);
+            assert_eq!(&after, board, "Unmaking {move} changed the board");
+        }
+    });
+}
+
+#[test]
+fn generated_moves_are_accepted() {
+    for_random_positions(|board, turn, _| {
+        for r#move in board.legal_moves(turn) {
+            let parsed =
+                Move::parse(&r#move.to_string(), board.size()).expect("Printed moves should parse");
+            assert_eq!(board.make_move_legal(parsed, turn), Ok(r#move));
+        }
+    });
+}
+
+#[test]
+fn pawns_can_always_win() {
+    for_random_positions(|board, turn, _| {
+        assert!(board.are_pawns_able_to_win());
+        let mut after = board.clone();
+        for r#move in board.legal_moves(turn) {
+            after.make_legal_move(r#move, turn);
+            assert!(after.are_pawns_able_to_win(), "{move} cut a pawn off");
+            after.unmake_legal_move(r#move, turn);
+        }
+    });
+}
+
+#[test]
+fn fence_counts_add_up() {
+    for_random_positions(|board, turn, fences| {
+        let placed = board.fences.iter().flatten().flatten().count();
+        let players = board.players();
+        let left: u32 = players
+            .iter()
+            .map(|&player| board.fences_left(player))
+            .sum();
+        assert_eq!(
+            u32::try_from(placed).expect("Small numbers fit") + left,
+            u32::try_from(players.len()).expect("Small numbers fit") * fences
+        );
+        for &player in players {
+            assert!(board.fences_left(player) <= fences);
+        }
+
+        let fence_moves = board
+            .legal_moves(turn)
+            .into_iter()
+            .filter_map(LegalMove::fence)
+            .count();
+        if board.fences_left(turn) == 0 {
+            assert_eq!(fence_moves, 0, "Fences were generated with none left");
+        }
+    });
+}
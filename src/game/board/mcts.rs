@@ -0,0 +1,264 @@
+use std::time::{Duration, Instant};
+
+use rand::{seq::SliceRandom, Rng};
+
+use super::{Board, LegalMove, Lmi};
+use crate::game::{Axis, PlayerColor};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MctsConfig {
+    pub iterations: u32,
+    /// The `c` in UCT, higher values explore more
+    pub exploration: f64,
+    /// Chance of a rollout move following the shortest path instead of being random
+    pub shortest_path_bias: f64,
+    /// Rollouts that don't end by then are won by the pawn closer to its goal
+    pub max_rollout_len: u32,
+    /// Stops the search early once this much time has passed, after at least one iteration
+    pub time: Option<Duration>,
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        Self {
+            iterations: 2_000,
+            exploration: std::f64::consts::SQRT_2,
+            shortest_path_bias: 0.8,
+            max_rollout_len: 200,
+            time: None,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Node {
+    /// The move leading here from the parent, and who made it
+    r#move: Option<(LegalMove, PlayerColor)>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// Moves not yet expanded into children, generated on the first visit
+    untried: Option<Vec<LegalMove>>,
+    visits: u32,
+    /// Rollouts won by the player who made `r#move`
+    wins: f64,
+}
+
+impl Node {
+    const fn new(r#move: Option<(LegalMove, PlayerColor)>, parent: Option<usize>) -> Self {
+        Self {
+            r#move,
+            parent,
+            children: vec![],
+            untried: None,
+            visits: 0,
+            wins: 0.0,
+        }
+    }
+
+    fn uct(&self, parent_visits: u32, exploration: f64) -> f64 {
+        let visits = f64::from(self.visits);
+        exploration.mul_add(
+            (f64::from(parent_visits).ln() / visits).sqrt(),
+            self.wins / visits,
+        )
+    }
+}
+
+impl Board {
+    /// Picks a move with Monte Carlo tree search, using UCT for selection
+    ///
+    /// A move that wins on the spot is played without searching, since rollouts
+    /// can barely tell it apart from the other moves that win a few plies later
+    pub fn mcts_best_move(
+        &self,
+        player: PlayerColor,
+        config: &MctsConfig,
+        rng: &mut impl Rng,
+    ) -> LegalMove {
+        let mut board = self.clone();
+        if let Some(r#move) = board.legal_pawn_moves(player).into_iter().find(|&r#move| {
+            board.make_legal_move(r#move, player);
+            let is_won = board.is_game_won().is_some();
+            board.unmake_legal_move(r#move, player);
+            is_won
+        }) {
+            return r#move;
+        }
+
+        let deadline = config.time.map(|time| Instant::now() + time);
+        let mut nodes = vec![Node::new(None, None)];
+        for iteration in 0..config.iterations.max(1) {
+            if iteration > 0 && deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                break;
+            }
+            let mut board = self.clone();
+            let mut to_move = player;
+            let mut current = 0;
+
+            // Selection
+            while nodes[current].untried.as_ref().is_some_and(Vec::is_empty)
+                && !nodes[current].children.is_empty()
+            {
+                let parent_visits = nodes[current].visits;
+                current = *nodes[current]
+                    .children
+                    .iter()
+                    .max_by(|&&a, &&b| {
+                        nodes[a]
+                            .uct(parent_visits, config.exploration)
+                            .total_cmp(&nodes[b].uct(parent_visits, config.exploration))
+                    })
+                    .expect("There are children");
+                let (r#move, mover) = nodes[current].r#move.expect("Only the root has no move");
+                board.make_legal_move(r#move, mover);
+                to_move = board.next_player_to_move(mover);
+            }
+
+            // Expansion
+            if board.is_game_won().is_none() {
+                let untried = nodes[current]
+                    .untried
+                    .get_or_insert_with(|| board.legal_moves(to_move));
+                if !untried.is_empty() {
+                    let r#move = untried.swap_remove(rng.gen_range(0..untried.len()));
+                    board.make_legal_move(r#move, to_move);
+                    nodes.push(Node::new(Some((r#move, to_move)), Some(current)));
+                    let child = nodes.len() - 1;
+                    nodes[current].children.push(child);
+                    current = child;
+                    to_move = board.next_player_to_move(to_move);
+                }
+            }
+
+            // Simulation
+            let winner = board.rollout(to_move, config, rng);
+
+            // Backpropagation
+            let mut node = Some(current);
+            while let Some(idx) = node {
+                nodes[idx].visits += 1;
+                if nodes[idx].r#move.is_some_and(|(_, mover)| mover == winner) {
+                    nodes[idx].wins += 1.0;
+                }
+                node = nodes[idx].parent;
+            }
+        }
+
+        nodes[0]
+            .children
+            .iter()
+            .max_by_key(|&&child| nodes[child].visits)
+            .and_then(|&child| nodes[child].r#move)
+            .map(|(r#move, _)| r#move)
+            .expect("Only players who can move are searched for")
+    }
+
+    /// Plays the game out from here with a cheap policy and returns the winner
+    fn rollout(
+        &mut self,
+        mut to_move: PlayerColor,
+        config: &MctsConfig,
+        rng: &mut impl Rng,
+    ) -> PlayerColor {
+        for _ in 0..config.max_rollout_len {
+            if let Some(winner) = self.is_game_won() {
+                return winner;
+            }
+            let r#move = if rng.gen_bool(config.shortest_path_bias) {
+                self.shortest_path_pawn_move(to_move)
+            } else {
+                None
+            }
+            .or_else(|| self.random_rollout_move(to_move, rng));
+            // A player who can't move passes
+            if let Some(r#move) = r#move {
+                self.make_legal_move(r#move, to_move);
+            }
+            to_move = self.next_player(to_move);
+        }
+        if let Some(winner) = self.is_game_won() {
+            return winner;
+        }
+        let distance = |player| self.shortest_path_len(player).unwrap_or(u32::MAX);
+        // Being to move sooner is worth one step, so ties go to whoever moves first
+        let mut winner = to_move;
+        let mut player = self.next_player(to_move);
+        while player != to_move {
+            if distance(player) < distance(winner) {
+                winner = player;
+            }
+            player = self.next_player(player);
+        }
+        winner
+    }
+
+    /// The pawn move that gets closest to the goal, if the pawn isn't boxed in
+    fn shortest_path_pawn_move(&mut self, player: PlayerColor) -> Option<LegalMove> {
+        self.legal_pawn_moves(player)
+            .into_iter()
+            .min_by_key(|&r#move| {
+                self.make_legal_move(r#move, player);
+                let distance = self.shortest_path_len(player);
+                self.unmake_legal_move(r#move, player);
+                distance.unwrap_or(u32::MAX)
+            })
+    }
+
+    /// A random pawn move or, half of the time, a random fence if one is found quickly
+    ///
+    /// A pawn boxed in by the others places any fence instead, and `None` means the player
+    /// can't move at all.
+    fn random_rollout_move(&self, player: PlayerColor, rng: &mut impl Rng) -> Option<LegalMove> {
+        if self.fences_left(player) > 0 && rng.gen_bool(0.5) {
+            for _ in 0..8 {
+                let axis = if rng.gen_bool(0.5) {
+                    Axis::Horizontal
+                } else {
+                    Axis::Vertical
+                };
+                let slots = self.size - 1;
+                let pos = (rng.gen_range(0..slots), rng.gen_range(0..slots));
+                if self.is_fence_move_legal(player, axis, pos).is_ok() {
+                    return Some(self.legal(Lmi::PlaceFence(axis, pos)));
+                }
+            }
+        }
+        let pawn_moves = self.legal_pawn_moves(player);
+        if let Some(&r#move) = pawn_moves.choose(rng) {
+            return Some(r#move);
+        }
+        self.legal_fence_moves(player).choose(rng).copied()
+    }
+}
+
+#[test]
+fn takes_the_winning_move() {
+    use rand::SeedableRng;
+    let mut board = Board::default();
+    board.move_pawn_unchecked(PlayerColor::White, (4, 8), (2, 1));
+    let config = MctsConfig {
+        iterations: 500,
+        ..MctsConfig::default()
+    };
+    let r#move = board.mcts_best_move(
+        PlayerColor::White,
+        &config,
+        &mut rand::rngs::StdRng::seed_from_u64(1),
+    );
+    assert_eq!(r#move, LegalMove(Lmi::MovePlayer((2, 1), (2, 0)), 9));
+}
+
+#[test]
+fn rollouts_pass_for_boxed_in_players() {
+    use rand::SeedableRng;
+    let board = super::invariants::boxed_in_white();
+    let config = MctsConfig {
+        iterations: 200,
+        ..MctsConfig::default()
+    };
+    let mut rng = rand::rngs::StdRng::seed_from_u64(1);
+    for _ in 0..20 {
+        board.clone().rollout(PlayerColor::White, &config, &mut rng);
+    }
+    board.mcts_best_move(PlayerColor::Green, &config, &mut rng);
+}
@@ -1,50 +1,91 @@
-use super::{LegalMove, Lmi};
+use rand::{seq::SliceRandom, Rng};
+
+use super::{pawn_move::Fail, LegalMove, Lmi};
 use crate::game::{Axis, Board, Direction, PlayerColor};
 impl Board {
+    /// Plays random moves from here, `turn` moving first, and calls `visit` on every position
+    /// reached along with the side to move, this one first. Stops once a pawn has won or after
+    /// `max_plies` moves, skipping the players who can't move like a `Game` does.
+    pub fn play_random_game(
+        &self,
+        turn: PlayerColor,
+        max_plies: usize,
+        rng: &mut impl Rng,
+        mut visit: impl FnMut(&Self, PlayerColor),
+    ) {
+        let mut board = self.clone();
+        let mut turn = board.first_able_to_move(turn);
+        for ply in 0..=max_plies {
+            visit(&board, turn);
+            if ply == max_plies || board.is_game_won().is_some() {
+                break;
+            }
+            let Some(&r#move) = board.legal_moves(turn).choose(rng) else {
+                break;
+            };
+            board.make_legal_move(r#move, turn);
+            turn = board.next_player_to_move(turn);
+        }
+    }
+
     pub fn legal_moves(&self, player: PlayerColor) -> Vec<LegalMove> {
-        let mut moves = vec![];
-        {
-            let player_pos = self.pawn_pos(player);
+        let mut moves = self.legal_pawn_moves(player);
+        moves.extend(self.legal_fence_moves(player));
+        moves
+    }
 
-            for dir in [
-                Direction::Left,
-                Direction::Right,
-                Direction::Up,
-                Direction::Down,
-            ] {
-                if self.is_obstructed(player_pos, dir) {
-                    continue;
-                }
-                let (x1, y1) = dir.offset(player_pos);
-                if self.squares[y1][x1].is_none() {
-                    moves.push(LegalMove(Lmi::MovePlayer(player_pos, (x1, y1))));
-                    continue;
-                }
-                if !self.is_obstructed((x1, y1), dir) {
-                    moves.push(LegalMove(Lmi::MovePlayer(player_pos, dir.offset((x1, y1)))));
-                    continue;
-                }
-                for sec_dir in dir.perpendiculars() {
-                    if !self.is_obstructed(player_pos, sec_dir) {
-                        moves.push(LegalMove(Lmi::MovePlayer(
-                            player_pos,
-                            sec_dir.offset((x1, y1)),
-                        )));
-                    }
-                }
-            }
+    pub fn legal_fence_moves(&self, player: PlayerColor) -> Vec<LegalMove> {
+        let mut moves = vec![];
+        if self.fences_left(player) == 0 {
+            return moves;
         }
-        for (y, row) in self.legal_fence_places.iter().enumerate() {
-            for (x, item) in row.iter().enumerate() {
+        let paths = self.shortest_paths();
+        let slots = self.size - 1;
+        for (y, row) in self.legal_fence_places.iter().take(slots).enumerate() {
+            for (x, item) in row.iter().take(slots).enumerate() {
                 for axis in [Axis::Horizontal, Axis::Vertical]
                     .into_iter()
                     .filter(|&axis| {
                         item.does_allow(axis)
-                            && self.is_fence_move_legal(player, axis, (x, y)).is_ok()
+                            && self
+                                .is_fence_move_legal_with_paths(player, axis, (x, y), &paths)
+                                .is_ok()
                     })
                 {
-                    moves.push(LegalMove(Lmi::PlaceFence(axis, (x, y))));
+                    moves.push(self.legal(Lmi::PlaceFence(axis, (x, y))));
+                }
+            }
+        }
+        moves
+    }
+
+    /// Every square the pawn can move to, found by trying each direction with
+    /// `pawn_move_destination` so that generation and validation can't disagree
+    pub fn legal_pawn_moves(&self, player: PlayerColor) -> Vec<LegalMove> {
+        let mut moves = vec![];
+        let player_pos = self.pawn_pos(player);
+
+        for dir in [
+            Direction::Left,
+            Direction::Right,
+            Direction::Up,
+            Direction::Down,
+        ] {
+            match self.pawn_move_destination(player, dir, None) {
+                Ok(pos) => moves.push(self.legal(Lmi::MovePlayer(player_pos, pos))),
+                Err(Fail::NoSecondary) => {
+                    for sec_dir in dir.perpendiculars() {
+                        if let Ok(pos) = self.pawn_move_destination(player, dir, Some(sec_dir)) {
+                            // With pawns on two sides, going around either can lead to the
+                            // same square
+                            let r#move = self.legal(Lmi::MovePlayer(player_pos, pos));
+                            if !moves.contains(&r#move) {
+                                moves.push(r#move);
+                            }
+                        }
+                    }
                 }
+                Err(_) => {}
             }
         }
         moves
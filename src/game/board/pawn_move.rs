@@ -1,13 +1,40 @@
-use super::super::{Axis, Board, Direction, PlayerColor};
+use core::fmt::Display;
+
+#[cfg(test)]
+use super::super::{Axis, Move};
+use super::super::{Board, Direction, PlayerColor};
+use super::zobrist;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Fail {
     PathObstructed,
     NoSecondary,
     InvalidSecondary,
+    /// The pawn can't get to the given square in one move
+    UnreachableSquare,
+    /// Another pawn is on the square the pawn would go around to
+    Occupied,
 }
+
+impl Display for Fail {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::PathObstructed => write!(f, "the path is obstructed"),
+            Self::NoSecondary => write!(
+                f,
+                "the pawn in the way can't be jumped over, so a second direction is needed"
+            ),
+            Self::InvalidSecondary => {
+                write!(f, "the second direction isn't perpendicular to the first")
+            }
+            Self::UnreachableSquare => write!(f, "the pawn can't reach that square in one move"),
+            Self::Occupied => write!(f, "another pawn is on that square"),
+        }
+    }
+}
+
 impl Board {
-    pub(super) fn move_pawn_unchecked(
+    pub(super) const fn move_pawn_unchecked(
         &mut self,
         pawn: PlayerColor,
         (xo, yo): (usize, usize),
@@ -15,12 +42,10 @@ impl Board {
     ) {
         self.squares[yo][xo] = None;
         self.squares[y][x] = Some(pawn);
-        match pawn {
-            PlayerColor::White => self.white_pawn = (x, y),
-            PlayerColor::Black => self.black_pawn = (x, y),
-        };
+        self.hash ^= zobrist::KEYS.pawn(pawn, (xo, yo)) ^ zobrist::KEYS.pawn(pawn, (x, y));
+        self.pawns[pawn as usize] = (x, y);
     }
-    pub(super) fn unmove_pawn_unchecked(
+    pub(super) const fn unmove_pawn_unchecked(
         &mut self,
         pawn: PlayerColor,
         (xo, yo): (usize, usize),
@@ -28,18 +53,21 @@ impl Board {
     ) {
         self.squares[y][x] = None;
         self.squares[yo][xo] = Some(pawn);
-        match pawn {
-            PlayerColor::White => self.white_pawn = (xo, yo),
-            PlayerColor::Black => self.black_pawn = (xo, yo),
-        };
+        self.hash ^= zobrist::KEYS.pawn(pawn, (x, y)) ^ zobrist::KEYS.pawn(pawn, (xo, yo));
+        self.pawns[pawn as usize] = (xo, yo);
     }
-    pub fn pawn_move_destination(
+    /// Where the pawn ends up moving in `dir`, jumping over the pawn in the way if there is one.
+    /// When a fence, the edge of the board or another pawn is behind that pawn, `second_dir` says
+    /// which way to go around it.
+    ///
+    /// This is the one place the jump rules live, `legal_pawn_moves` is built on top of it.
+    pub const fn pawn_move_destination(
         &self,
         player: PlayerColor,
         dir: Direction,
         second_dir: Option<Direction>,
     ) -> Result<(usize, usize), Fail> {
-        use Fail::{InvalidSecondary, NoSecondary, PathObstructed};
+        use Fail::{InvalidSecondary, NoSecondary, Occupied, PathObstructed};
         let (x, y) = self.pawn_pos(player);
 
         if self.is_obstructed((x, y), dir) {
@@ -53,7 +81,9 @@ impl Board {
 
         if !self.is_obstructed((x1, y1), dir) {
             let (x2, y2) = dir.offset((x1, y1));
-            return Ok((x2, y2));
+            if self.squares[y2][x2].is_none() {
+                return Ok((x2, y2));
+            }
         }
 
         let Some(sec_dir) = second_dir else {
@@ -62,36 +92,220 @@ impl Board {
 
         if dir.are_parallel(sec_dir) {
             return Err(InvalidSecondary);
-        };
+        }
 
         if self.is_obstructed((x1, y1), sec_dir) {
             return Err(PathObstructed);
         }
-        Ok(sec_dir.offset((x1, y1)))
+        let (x2, y2) = sec_dir.offset((x1, y1));
+        if self.squares[y2][x2].is_some() {
+            return Err(Occupied);
+        }
+        Ok((x2, y2))
     }
 
-    pub fn is_obstructed(&self, (x, y): (usize, usize), dir: Direction) -> bool {
-        match dir {
-            Direction::Left => {
-                x == 0
-                    || y < 8 && self.fences[y][x - 1] == Some(Axis::Vertical)
-                    || y > 0 && self.fences[y - 1][x - 1] == Some(Axis::Vertical)
+    pub const fn is_obstructed(&self, pos: (usize, usize), dir: Direction) -> bool {
+        self.walls.is_obstructed(pos, dir)
+    }
+}
+
+#[cfg(test)]
+const DIRECTIONS: [Direction; 4] = [
+    Direction::Left,
+    Direction::Right,
+    Direction::Up,
+    Direction::Down,
+];
+
+/// The fence slots that would block the side of `(x, y)` facing `dir` on a board of `size`
+#[cfg(test)]
+fn blocking_slots(
+    (x, y): (usize, usize),
+    dir: Direction,
+    size: usize,
+) -> Vec<(Axis, (usize, usize))> {
+    let (axis, fixed, along) = match dir {
+        Direction::Right if x + 1 < size => (Axis::Vertical, x, y),
+        Direction::Left if x > 0 => (Axis::Vertical, x - 1, y),
+        Direction::Down if y + 1 < size => (Axis::Horizontal, y, x),
+        Direction::Up if y > 0 => (Axis::Horizontal, y - 1, x),
+        _ => return vec![],
+    };
+    [
+        along.checked_sub(1),
+        Some(along).filter(|&along| along + 1 < size),
+    ]
+    .into_iter()
+    .flatten()
+    .map(|along| match axis {
+        Axis::Horizontal => (axis, (along, fixed)),
+        Axis::Vertical => (axis, (fixed, along)),
+    })
+    .collect()
+}
+
+/// The squares White can move to by the official rules, written in terms of squares rather
+/// than directions
+#[cfg(test)]
+fn official_destinations(board: &Board) -> Vec<(usize, usize)> {
+    let open = |from, to| {
+        DIRECTIONS
+            .into_iter()
+            .any(|dir| !board.is_obstructed(from, dir) && dir.offset(from) == to)
+    };
+    let (player, opponent) = (
+        board.pawn_pos(PlayerColor::White),
+        board.pawn_pos(PlayerColor::Black),
+    );
+    let behind = (2 * opponent.0)
+        .checked_sub(player.0)
+        .zip((2 * opponent.1).checked_sub(player.1))
+        .filter(|&(x, y)| x < board.size && y < board.size);
+    let can_jump_straight = behind.is_some_and(|behind| open(opponent, behind));
+
+    let mut destinations = vec![];
+    for y in 0..board.size {
+        for x in 0..board.size {
+            let square = (x, y);
+            if square == player || square == opponent {
+                continue;
             }
-            Direction::Right => {
-                x == 8
-                    || y < 8 && self.fences[y][x] == Some(Axis::Vertical)
-                    || y > 0 && self.fences[y - 1][x] == Some(Axis::Vertical)
+            let step = open(player, square);
+            let jump = open(player, opponent)
+                && open(opponent, square)
+                && (Some(square) == behind || !can_jump_straight);
+            if step || jump {
+                destinations.push(square);
             }
-            Direction::Down => {
-                y == 8
-                    || x < 8 && self.fences[y][x] == Some(Axis::Horizontal)
-                    || x > 0 && self.fences[y][x - 1] == Some(Axis::Horizontal)
+        }
+    }
+    destinations
+}
+
+#[test]
+fn generation_and_validation_follow_the_jump_rules() {
+    for size in [9, 5] {
+        check_jump_rules(&Board::new(super::BoardConfig {
+            size,
+            ..super::BoardConfig::default()
+        }));
+    }
+}
+
+/// Tries every placement of the two pawns next to each other on `empty`, with every way of
+/// fencing in the opponent
+#[cfg(test)]
+fn check_jump_rules(empty: &Board) {
+    let size = empty.size;
+    for player in (0..size * size).map(|square| (square % size, square / size)) {
+        for opponent in DIRECTIONS
+            .into_iter()
+            .filter(|&dir| !empty.is_obstructed(player, dir))
+            .map(|dir| dir.offset(player))
+        {
+            // Every way of blocking or leaving open each side of the opponent
+            let mut configurations = vec![vec![]];
+            for side in DIRECTIONS {
+                let slots = blocking_slots(opponent, side, size);
+                configurations = configurations
+                    .into_iter()
+                    .flat_map(|fences: Vec<_>| {
+                        core::iter::once(None)
+                            .chain(slots.iter().copied().map(Some))
+                            .map(move |slot| {
+                                let mut fences = fences.clone();
+                                fences.extend(slot);
+                                fences
+                            })
+                    })
+                    .collect();
             }
-            Direction::Up => {
-                y == 0
-                    || x < 8 && self.fences[y - 1][x] == Some(Axis::Horizontal)
-                    || x > 0 && self.fences[y - 1][x - 1] == Some(Axis::Horizontal)
+
+            for fences in configurations {
+                let mut board = Board {
+                    squares: Default::default(),
+                    ..empty.clone()
+                };
+                board.pawns[PlayerColor::White as usize] = player;
+                board.pawns[PlayerColor::Black as usize] = opponent;
+                board.squares[player.1][player.0] = Some(PlayerColor::White);
+                board.squares[opponent.1][opponent.0] = Some(PlayerColor::Black);
+                if fences
+                    .iter()
+                    .any(|&(axis, (x, y))| !board.legal_fence_places[y][x].does_allow(axis))
+                {
+                    continue;
+                }
+                for &(axis, pos) in &fences {
+                    board.place_fence(axis, pos);
+                }
+
+                let official = official_destinations(&board);
+                let mut generated: Vec<_> = board
+                    .legal_pawn_moves(PlayerColor::White)
+                    .into_iter()
+                    .filter_map(super::LegalMove::destination)
+                    .collect();
+                generated.sort_by_key(|&(x, y)| (y, x));
+                assert_eq!(
+                    generated,
+                    official,
+                    "{}",
+                    board.to_position_str(PlayerColor::White)
+                );
+
+                for dir in DIRECTIONS {
+                    for second_dir in core::iter::once(None).chain(DIRECTIONS.map(Some)) {
+                        if let Ok(pos) =
+                            board.pawn_move_destination(PlayerColor::White, dir, second_dir)
+                        {
+                            assert!(official.contains(&pos));
+                        }
+                    }
+                }
+                for &pos in &official {
+                    assert!(board
+                        .make_move_legal(Move::PawnTo(pos), PlayerColor::White)
+                        .is_ok());
+                }
             }
         }
     }
 }
+
+#[test]
+fn jumps_around_several_pawns() {
+    let (board, player) =
+        Board::from_position_str("e5 e6 e7 d6 - 5 5 5 5 w").expect("The position is valid");
+    let destinations = |board: &Board, player| {
+        let mut destinations: Vec<_> = board
+            .legal_pawn_moves(player)
+            .into_iter()
+            .filter_map(super::LegalMove::destination)
+            .collect();
+        destinations.sort_unstable();
+        destinations
+    };
+    // Red behind Black rules out the straight jump, and Green takes up one of the side-steps
+    assert_eq!(
+        destinations(&board, player),
+        vec![(3, 4), (4, 5), (5, 3), (5, 4)]
+    );
+    assert_eq!(
+        board.pawn_move_destination(player, Direction::Up, Some(Direction::Left)),
+        Err(Fail::Occupied)
+    );
+
+    let (board, player) =
+        Board::from_position_str("e5 e6 a5 i5 - 5 5 5 5 w").expect("The position is valid");
+    assert!(destinations(&board, player).contains(&(4, 2)));
+    assert!(board.make_move_legal(Move::PawnTo((5, 3)), player).is_err());
+
+    // Going around White on the left and around Black above both lead to d5, which is one move
+    let (board, player) =
+        Board::from_position_str("7x7 d4 e5 c4 e4 d5h 2 2 1 2 g").expect("The position is valid");
+    assert_eq!(
+        destinations(&board, player),
+        vec![(3, 2), (3, 4), (4, 4), (5, 2), (5, 3)]
+    );
+}
@@ -0,0 +1,325 @@
+use super::{Board, LegalMove};
+#[cfg(test)]
+use crate::game::Axis;
+use crate::game::PlayerColor;
+
+impl Board {
+    /// Counts the move sequences of length `depth` from this position, `player` moving first
+    ///
+    /// Once a pawn reaches its goal the game is over and there are no more moves, so lines
+    /// that end the game early aren't counted. A player who can't move passes, which counts as
+    /// one move.
+    pub fn perft(&self, player: PlayerColor, depth: u32) -> u64 {
+        self.clone().perft_inner(player, depth)
+    }
+
+    /// Like `perft`, but split up by the first move, of which there are none at depth 0
+    pub fn perft_divide(&self, player: PlayerColor, depth: u32) -> Vec<(LegalMove, u64)> {
+        let mut board = self.clone();
+        if depth == 0 || board.is_game_won().is_some() {
+            return vec![];
+        }
+        board
+            .legal_moves(player)
+            .into_iter()
+            .map(|r#move| {
+                board.make_legal_move(r#move, player);
+                let count = board.perft_inner(board.next_player(player), depth - 1);
+                board.unmake_legal_move(r#move, player);
+                (r#move, count)
+            })
+            .collect()
+    }
+
+    fn perft_inner(&mut self, player: PlayerColor, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        if self.is_game_won().is_some() {
+            return 0;
+        }
+        let moves = self.legal_moves(player);
+        if moves.is_empty() {
+            return self.perft_inner(self.next_player(player), depth - 1);
+        }
+        if depth == 1 {
+            return moves.len() as u64;
+        }
+        moves
+            .into_iter()
+            .map(|r#move| {
+                self.make_legal_move(r#move, player);
+                let count = self.perft_inner(self.next_player(player), depth - 1);
+                self.unmake_legal_move(r#move, player);
+                count
+            })
+            .sum()
+    }
+}
+/// Leaf counts of a few positions, to check changes to move generation against
+///
+/// Every count is checked by `agrees_with_a_brute_force_count` against `NaivePosition`, which
+/// follows the rules with nothing but a list of fences and a breadth-first search, so the counts
+/// don't just repeat what the move generation says. The largest ones take a while and are only
+/// counted by `cargo test --release -- --ignored`.
+#[cfg(test)]
+const REFERENCE_COUNTS: &[(&str, &[u64])] = &[
+    ("e1 e9 - 10 10 w", &[131, 16_677, 2_062_264]),
+    // The pawns face each other with a fence behind Black, so White can only jump sideways
+    ("e5 e6 e6h 10 10 w", &[129, 15_922, 1_936_376]),
+    // A fence beside the opponent rules out one of the side-steps
+    ("e5 e6 e6h,e5v 10 10 w", &[124, 14_800, 1_726_964]),
+    // The fence beside White doesn't stop it from side-stepping around Black
+    ("e5 e6 e6h,d4v 3 0 w", &[124, 368, 43_964]),
+    ("c8 a9 a8h,b7v 0 1 b", &[122, 362, 485, 1_674, 4_324]),
+    // Black can win by jumping sideways past White
+    ("e1 e2 - 0 0 b", &[5, 9, 38, 96]),
+    ("5x5 c1 c5 - 3 3 w", &[35, 1_109, 31_540]),
+    ("11x11 f1 f11 - 10 10 w", &[203, 40_445]),
+    ("e1 e9 a5 i5 - 5 5 5 5 w", &[131, 16_677, 2_062_065]),
+    // Red can't jump over White onto Green, and Black is in the way of one side-step
+    ("7x7 d4 d5 c4 e4 - 2 2 2 2 r", &[76, 5_502, 379_144]),
+];
+
+#[test]
+fn matches_reference_counts() {
+    for (position, counts) in REFERENCE_COUNTS {
+        let (board, player) = Board::from_position_str(position).expect("The position is valid");
+        for (depth, &count) in (1..).zip(counts.iter()) {
+            assert_eq!(
+                board.perft(player, depth),
+                count,
+                "{position} at depth {depth}"
+            );
+        }
+        let divided = board.perft_divide(player, 2);
+        assert_eq!(
+            divided.iter().map(|(_, count)| count).sum::<u64>(),
+            counts[1]
+        );
+    }
+}
+
+#[test]
+fn boxed_in_players_pass() {
+    let (board, player) = (super::invariants::boxed_in_white(), PlayerColor::White);
+    assert!(!board.can_move(player));
+    assert_eq!(board.perft(player, 1), 1);
+    assert_eq!(
+        board.perft(player, 2),
+        board.perft(PlayerColor::Red, 1),
+        "Red moves after White passes"
+    );
+}
+
+/// A position kept as plainly as the rules are written, sharing no code with `Board` beyond
+/// being read off one, to count moves independently of the move generation
+#[cfg(test)]
+#[derive(Clone)]
+struct NaivePosition {
+    size: usize,
+    /// The players in turn order, with the square of their pawn and the fences they have left
+    players: Vec<(PlayerColor, (usize, usize), u32)>,
+    fences: Vec<(Axis, (usize, usize))>,
+}
+
+#[cfg(test)]
+const STEPS: [(isize, isize); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+
+#[cfg(test)]
+impl NaivePosition {
+    fn new(board: &Board) -> Self {
+        let size = board.size();
+        let players = board
+            .players()
+            .iter()
+            .map(|&player| (player, board.pawn_pos(player), board.fences_left(player)))
+            .collect();
+        let mut fences = vec![];
+        for y in 0..size - 1 {
+            for x in 0..size - 1 {
+                fences.extend(board.fence_at((x, y)).map(|axis| (axis, (x, y))));
+            }
+        }
+        Self {
+            size,
+            players,
+            fences,
+        }
+    }
+
+    /// The square one step away, unless the edge of the board or a fence is in between
+    fn step(&self, (x, y): (usize, usize), (dx, dy): (isize, isize)) -> Option<(usize, usize)> {
+        let to = (x.checked_add_signed(dx)?, y.checked_add_signed(dy)?);
+        if to.0 >= self.size || to.1 >= self.size {
+            return None;
+        }
+        // A fence in the slot at `(x, y)` runs along the lines past squares `x` and `x + 1`, or
+        // rows `y` and `y + 1`, right of or below them
+        let is_fenced = |axis, (x, y): (usize, usize)| self.fences.contains(&(axis, (x, y)));
+        let blocked = if dx == 0 {
+            let row = y.min(to.1);
+            is_fenced(Axis::Horizontal, (x, row))
+                || x > 0 && is_fenced(Axis::Horizontal, (x - 1, row))
+        } else {
+            let column = x.min(to.0);
+            is_fenced(Axis::Vertical, (column, y))
+                || y > 0 && is_fenced(Axis::Vertical, (column, y - 1))
+        };
+        (!blocked).then_some(to)
+    }
+
+    fn is_occupied(&self, square: (usize, usize)) -> bool {
+        self.players.iter().any(|&(_, pawn, _)| pawn == square)
+    }
+
+    fn is_goal(&self, player: PlayerColor, (x, y): (usize, usize)) -> bool {
+        match player {
+            PlayerColor::White => y == 0,
+            PlayerColor::Black => y == self.size - 1,
+            PlayerColor::Red => x == self.size - 1,
+            PlayerColor::Green => x == 0,
+        }
+    }
+
+    fn can_reach_goal(&self, player: PlayerColor, from: (usize, usize)) -> bool {
+        let mut seen = vec![vec![false; self.size]; self.size];
+        seen[from.1][from.0] = true;
+        let mut queue = std::collections::VecDeque::from([from]);
+        while let Some(square) = queue.pop_front() {
+            if self.is_goal(player, square) {
+                return true;
+            }
+            for step in STEPS {
+                if let Some((x, y)) = self.step(square, step).filter(|&(x, y)| !seen[y][x]) {
+                    seen[y][x] = true;
+                    queue.push_back((x, y));
+                }
+            }
+        }
+        false
+    }
+
+    /// The positions after each legal move of the player at `turn` in the turn order
+    fn children(&self, turn: usize) -> Vec<Self> {
+        let (_, from, fences_left) = self.players[turn];
+        let mut destinations = vec![];
+        for step in STEPS {
+            let Some(next) = self.step(from, step) else {
+                continue;
+            };
+            if !self.is_occupied(next) {
+                destinations.push(next);
+                continue;
+            }
+            // A pawn in the way is jumped over, or gone around if that can't be done
+            if let Some(jump) = self
+                .step(next, step)
+                .filter(|&jump| !self.is_occupied(jump))
+            {
+                destinations.push(jump);
+                continue;
+            }
+            for side in [(step.1, step.0), (-step.1, -step.0)] {
+                if let Some(around) = self.step(next, side) {
+                    if !self.is_occupied(around) && !destinations.contains(&around) {
+                        destinations.push(around);
+                    }
+                }
+            }
+        }
+        let mut children: Vec<_> = destinations
+            .into_iter()
+            .map(|to| {
+                let mut child = self.clone();
+                child.players[turn].1 = to;
+                child
+            })
+            .collect();
+
+        if fences_left == 0 {
+            return children;
+        }
+        for y in 0..self.size - 1 {
+            for x in 0..self.size - 1 {
+                for axis in [Axis::Horizontal, Axis::Vertical] {
+                    let (before, after) = match axis {
+                        Axis::Horizontal => ((x.wrapping_sub(1), y), (x + 1, y)),
+                        Axis::Vertical => ((x, y.wrapping_sub(1)), (x, y + 1)),
+                    };
+                    let collides = self.fences.iter().any(|&(other, slot)| {
+                        slot == (x, y) || other == axis && (slot == before || slot == after)
+                    });
+                    if collides {
+                        continue;
+                    }
+                    let mut child = self.clone();
+                    child.fences.push((axis, (x, y)));
+                    child.players[turn].2 -= 1;
+                    if child
+                        .players
+                        .iter()
+                        .all(|&(player, pawn, _)| child.can_reach_goal(player, pawn))
+                    {
+                        children.push(child);
+                    }
+                }
+            }
+        }
+        children
+    }
+
+    /// Like `Board::perft`, for the player at `turn` in the turn order
+    fn count(&self, turn: usize, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+        if self
+            .players
+            .iter()
+            .any(|&(player, pawn, _)| self.is_goal(player, pawn))
+        {
+            return 0;
+        }
+        let next = (turn + 1) % self.players.len();
+        let children = self.children(turn);
+        if children.is_empty() {
+            return self.count(next, depth - 1);
+        }
+        children
+            .iter()
+            .map(|child| child.count(next, depth - 1))
+            .sum()
+    }
+}
+
+/// Checks the reference counts up to `limit` leaves against `NaivePosition`
+#[cfg(test)]
+fn check_reference_counts_by_brute_force(limit: u64) {
+    for (position, counts) in REFERENCE_COUNTS {
+        let (board, player) = Board::from_position_str(position).expect("The position is valid");
+        let naive = NaivePosition::new(&board);
+        let turn = board.turn_index(player);
+        for (depth, &count) in (1..)
+            .zip(counts.iter())
+            .filter(|&(_, &count)| count <= limit)
+        {
+            assert_eq!(
+                naive.count(turn, depth),
+                count,
+                "{position} at depth {depth}"
+            );
+        }
+    }
+}
+
+#[test]
+fn agrees_with_a_brute_force_count() {
+    check_reference_counts_by_brute_force(10_000);
+}
+
+#[test]
+#[ignore = "counts millions of leaves without the move generation's shortcuts"]
+fn agrees_with_a_brute_force_count_in_full() {
+    check_reference_counts_by_brute_force(u64::MAX);
+}
@@ -0,0 +1,231 @@
+//! A compact textual form of a position, made of six space separated fields:
+//! White's pawn, Black's pawn, the placed fences separated by commas (or `-` if there are none),
+//! White's fences left, Black's fences left and the side to move (`w` or `b`).
+//!
+//! Squares and fences are written in the standard notation, so the starting position is
+//! `e1 e9 - 10 10 w`. Positions on other board sizes start with an extra field for the size,
+//! like `5x5 c1 c5 - 5 5 w`. Four-player positions have the pawns and fence counts of Red and
+//! Green after those of White and Black, with `r` and `g` for them to move, like
+//! `e1 e9 a5 i5 - 5 5 5 5 r`.
+use core::fmt::Display;
+
+use super::super::{notation, Move, PlayerColor};
+use super::{Board, BoardConfig, STANDARD_SIZE};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PositionError {
+    WrongFieldCount(usize),
+    InvalidSize(String),
+    InvalidSquare(String),
+    PawnsOnSameSquare,
+    InvalidFence(String),
+    /// The fence overlaps or crosses one placed before it
+    CollidingFence(String),
+    InvalidFenceCount(String),
+    InvalidSideToMove(String),
+    /// At least one of the pawns can't reach its goal row
+    NoPathRemaining,
+}
+
+impl Display for PositionError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::WrongFieldCount(count) => write!(
+                f,
+                "expected 6 fields, or 10 with four players, and one more for the size, \
+                 found {count}"
+            ),
+            Self::InvalidSize(size) => write!(f, "`{size}` is not a board size, like `7x7`"),
+            Self::InvalidSquare(square) => write!(f, "`{square}` is not a square"),
+            Self::PawnsOnSameSquare => write!(f, "both pawns are on the same square"),
+            Self::InvalidFence(fence) => write!(f, "`{fence}` is not a fence"),
+            Self::CollidingFence(fence) => {
+                write!(f, "the fence `{fence}` collides with another fence")
+            }
+            Self::InvalidFenceCount(count) => {
+                write!(f, "`{count}` is not a valid number of fences")
+            }
+            Self::InvalidSideToMove(side) => write!(f, "`{side}` is not a player in the game"),
+            Self::NoPathRemaining => write!(f, "a pawn has no path to its goal"),
+        }
+    }
+}
+
+impl std::error::Error for PositionError {}
+
+impl Board {
+    /// Parses a position string, returning the board and the side to move
+    pub fn from_position_str(position: &str) -> Result<(Self, PlayerColor), PositionError> {
+        let mut fields: Vec<_> = position.split_whitespace().collect();
+        let size = if matches!(fields.len(), 7 | 11) {
+            let size = fields.remove(0);
+            size.split_once('x')
+                .filter(|(columns, rows)| columns == rows)
+                .and_then(|(columns, _)| columns.parse().ok())
+                .filter(|&size| BoardConfig::is_valid_size(size))
+                .ok_or_else(|| PositionError::InvalidSize(size.to_owned()))?
+        } else {
+            STANDARD_SIZE
+        };
+        let players = match fields.len() {
+            6 => 2,
+            10 => 4,
+            count => return Err(PositionError::WrongFieldCount(count)),
+        };
+        let (pawns, rest) = fields.split_at(players);
+        let (&[fences], rest) = rest.split_at(1) else {
+            unreachable!("There are enough fields")
+        };
+        let (fence_counts, &[to_move]) = rest.split_at(players) else {
+            unreachable!("There are enough fields")
+        };
+
+        let square = |square: &str| match notation::parse_move(square, size) {
+            Ok(Move::PawnTo(pos)) => Ok(pos),
+            _ => Err(PositionError::InvalidSquare(square.to_owned())),
+        };
+        let fence_count = |count: &str| {
+            count
+                .parse()
+                .ok()
+                .filter(|&count| BoardConfig::is_valid_fence_count(count))
+                .ok_or_else(|| PositionError::InvalidFenceCount(count.to_owned()))
+        };
+
+        let mut board = Self {
+            squares: Default::default(),
+            ..Self::new(BoardConfig {
+                size,
+                players,
+                ..BoardConfig::default()
+            })
+        };
+        for (&player, pawn) in PlayerColor::ALL.iter().zip(pawns) {
+            let (x, y) = square(pawn)?;
+            if board.squares[y][x].is_some() {
+                return Err(PositionError::PawnsOnSameSquare);
+            }
+            board.pawns[player as usize] = (x, y);
+            board.squares[y][x] = Some(player);
+        }
+
+        for fence in fences.split(',').filter(|&fence| fence != "-") {
+            let Ok(Move::PlaceFence(axis, (x, y))) = notation::parse_move(fence, size) else {
+                return Err(PositionError::InvalidFence(fence.to_owned()));
+            };
+            if !board.legal_fence_places[y][x].does_allow(axis) {
+                return Err(PositionError::CollidingFence(fence.to_owned()));
+            }
+            board.place_fence(axis, (x, y));
+        }
+        if !board.are_pawns_able_to_win() {
+            return Err(PositionError::NoPathRemaining);
+        }
+
+        for (&player, count) in PlayerColor::ALL.iter().zip(fence_counts) {
+            board.fences_left[player as usize] = fence_count(count)?;
+        }
+        let to_move = board
+            .players
+            .iter()
+            .copied()
+            .find(|player| to_move.len() == 1 && to_move.starts_with(player.letter()))
+            .ok_or_else(|| PositionError::InvalidSideToMove(to_move.to_owned()))?;
+        board.hash = board.compute_hash();
+        Ok((board, to_move))
+    }
+
+    pub fn to_position_str(&self, to_move: PlayerColor) -> String {
+        let mut fences = vec![];
+        for (y, row) in self.fences.iter().enumerate() {
+            for (x, fence) in row.iter().enumerate() {
+                if let Some(axis) = fence {
+                    fences.push(notation::fence_name(*axis, (x, y), self.size));
+                }
+            }
+        }
+        let mut fields = vec![];
+        if self.size != STANDARD_SIZE {
+            fields.push(format!("{0}x{0}", self.size));
+        }
+        // Pawns and fence counts are written in the order of `PlayerColor`, not the turn order
+        let players = &PlayerColor::ALL[..self.players.len()];
+        for &player in players {
+            fields.push(notation::square_name(self.pawn_pos(player), self.size));
+        }
+        fields.push(if fences.is_empty() {
+            "-".to_owned()
+        } else {
+            fences.join(",")
+        });
+        for &player in players {
+            fields.push(self.fences_left(player).to_string());
+        }
+        fields.push(to_move.letter().to_string());
+        fields.join(" ")
+    }
+}
+
+#[test]
+fn position_strings_round_trip() {
+    let default = Board::from_position_str("e1 e9 - 10 10 w");
+    assert_eq!(default, Ok((Board::default(), PlayerColor::White)));
+
+    let position = "d5 e6 e5h,c3v,e3h 7 9 b";
+    let (board, to_move) = Board::from_position_str(position).expect("The position is valid");
+    assert_eq!(board.to_position_str(to_move), position);
+
+    assert_eq!(
+        Board::from_position_str("e1 e9 e3h,f3h 10 10 w"),
+        Err(PositionError::CollidingFence("f3h".to_owned()))
+    );
+    assert_eq!(
+        Board::from_position_str("e1 e9 a1h,c1h,e1h,g1h,h2v,h1h 10 10 w"),
+        Err(PositionError::CollidingFence("h1h".to_owned()))
+    );
+    assert_eq!(
+        Board::from_position_str("a1 e9 a1v,a2h 10 10 w"),
+        Err(PositionError::NoPathRemaining)
+    );
+
+    let small = Board::from_position_str("5x5 c1 c5 - 5 5 w");
+    assert_eq!(
+        small,
+        Ok((
+            Board::new(BoardConfig {
+                size: 5,
+                fences: 5,
+                players: 2
+            }),
+            PlayerColor::White
+        ))
+    );
+    let position = "11x11 f1 k11 a10h,j1v 0 3 b";
+    let (board, to_move) = Board::from_position_str(position).expect("The position is valid");
+    assert_eq!(board.to_position_str(to_move), position);
+    assert_eq!(
+        Board::from_position_str("5x5 e1 e9 - 10 10 w"),
+        Err(PositionError::InvalidSquare("e9".to_owned()))
+    );
+    assert_eq!(
+        Board::from_position_str("6x6 c1 c5 - 5 5 w"),
+        Err(PositionError::InvalidSize("6x6".to_owned()))
+    );
+
+    let four_players = Board::from_position_str("e1 e9 a5 i5 - 5 5 5 5 w");
+    assert_eq!(
+        four_players,
+        Ok((Board::new(BoardConfig::with_players(4)), PlayerColor::White))
+    );
+    let position = "7x7 d2 d7 b4 g4 a6h 4 5 5 5 g";
+    let (board, to_move) = Board::from_position_str(position).expect("The position is valid");
+    assert_eq!(board.to_position_str(to_move), position);
+    assert_eq!(
+        Board::from_position_str("e1 e9 - 10 10 r"),
+        Err(PositionError::InvalidSideToMove("r".to_owned()))
+    );
+    assert_eq!(
+        Board::from_position_str("e1 e9 e1 i5 - 5 5 5 5 w"),
+        Err(PositionError::PawnsOnSameSquare)
+    );
+}
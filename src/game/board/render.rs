@@ -0,0 +1,350 @@
+//! Pictures of the board made of text, drawn with box-drawing characters or plain ASCII, with or
+//! without colors and coordinates, and seen from either White's or Black's side
+//!
+//! `Display` for `Board` draws it with the default `RenderOptions`, which is the best look on a
+//! terminal. `RenderOptions::detect` tones it down for logs, pipes and dumb terminals.
+use core::fmt::{Display, Formatter, Result};
+use std::io::IsTerminal;
+
+use super::super::{notation, Axis};
+use super::Board;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Charset {
+    /// Box-drawing characters, with heavy lines for the fences
+    #[default]
+    Unicode,
+    /// `+`, `-` and `|` for the grid, with `=` and `#` for the fences
+    Ascii,
+}
+
+/// Coordinates written on and around the board, matching the notation
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Labels {
+    Off,
+    /// The numbers of the rows along the left edge and the letters of the columns along the
+    /// bottom edge
+    #[default]
+    Squares,
+    /// The same as `Squares`, with the name of each fence slot also written on the line above
+    /// its lower left square, just left of the slot's center, like `e3` for where `e3h` and
+    /// `e3v` go
+    SquaresAndSlots,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderOptions {
+    pub charset: Charset,
+    /// Whether the fences are colored with ANSI escapes
+    pub color: bool,
+    pub labels: Labels,
+    /// Whether the board is turned around, with Black's side at the bottom
+    pub flipped: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            charset: Charset::Unicode,
+            color: true,
+            labels: Labels::Squares,
+            flipped: false,
+        }
+    }
+}
+
+impl RenderOptions {
+    /// The default options when standard output is a terminal, and plain ASCII without colors
+    /// when it isn't or the terminal is dumb. Colors are also left out if `NO_COLOR` is set.
+    pub fn detect() -> Self {
+        let terminal = std::io::stdout().is_terminal()
+            && !std::env::var("TERM").is_ok_and(|term| term == "dumb");
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        Self {
+            charset: if terminal {
+                Charset::Unicode
+            } else {
+                Charset::Ascii
+            },
+            color: terminal && !no_color,
+            ..Self::default()
+        }
+    }
+}
+
+/// Characters the board is drawn with
+struct Glyphs {
+    /// Left corner, joint and right corner of the top edge
+    top: [char; 3],
+    /// Left edge, joint and right edge of the lines in between rows
+    middle: [char; 3],
+    /// Left corner, joint and right corner of the bottom edge
+    bottom: [char; 3],
+    line: char,
+    bar: char,
+    fence_line: char,
+    fence_bar: char,
+}
+
+const UNICODE: Glyphs = Glyphs {
+    top: ['╭', '┬', '╮'],
+    middle: ['├', '┼', '┤'],
+    bottom: ['╰', '┴', '╯'],
+    line: '─',
+    bar: '│',
+    fence_line: '━',
+    fence_bar: '┃',
+};
+
+const ASCII: Glyphs = Glyphs {
+    top: ['+'; 3],
+    middle: ['+'; 3],
+    bottom: ['+'; 3],
+    line: '-',
+    bar: '|',
+    fence_line: '=',
+    fence_bar: '#',
+};
+
+/// A line of the picture, where each character is marked with whether it is part of a fence
+#[derive(Default)]
+struct Line(Vec<(char, bool)>);
+
+impl Line {
+    fn push(&mut self, text: &str, fence: bool) {
+        self.0.extend(text.chars().map(|c| (c, fence)));
+    }
+
+    fn push_char(&mut self, c: char, count: usize, fence: bool) {
+        self.0.extend(std::iter::repeat_n((c, fence), count));
+    }
+
+    /// The top or bottom edge of a board with `size` squares a side, where `corners` are the
+    /// left corner, the joints and the right corner
+    fn edge(corners: [char; 3], line: char, size: usize) -> Self {
+        let mut edge = Self::default();
+        edge.push("   ", false);
+        edge.push_char(corners[0], 1, false);
+        for _ in 1..size {
+            edge.push_char(line, 3, false);
+            edge.push_char(corners[1], 1, false);
+        }
+        edge.push_char(line, 3, false);
+        edge.push_char(corners[2], 1, false);
+        edge
+    }
+
+    fn write(&self, f: &mut Formatter<'_>, color: bool) -> Result {
+        for run in self.0.chunk_by(|a, b| a.1 == b.1) {
+            let text: String = run.iter().map(|&(c, _)| c).collect();
+            if color && run[0].1 {
+                write!(f, "\x1b[31m{text}\x1b[0m")?;
+            } else {
+                write!(f, "{text}")?;
+            }
+        }
+        writeln!(f)
+    }
+}
+
+/// The board drawn with some `RenderOptions`
+pub struct Rendered<'a> {
+    board: &'a Board,
+    options: RenderOptions,
+}
+
+impl Board {
+    pub const fn render(&self, options: RenderOptions) -> Rendered<'_> {
+        Rendered {
+            board: self,
+            options,
+        }
+    }
+}
+
+impl Display for Board {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        self.render(RenderOptions::default()).fmt(f)
+    }
+}
+
+impl Display for Rendered<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let (board, options) = (self.board, self.options);
+        let glyphs = match options.charset {
+            Charset::Unicode => &UNICODE,
+            Charset::Ascii => &ASCII,
+        };
+        // Everything below is drawn as seen, and turning the board around turns the squares and
+        // fence slots around its center
+        let last = board.size - 1;
+        let turn = |(x, y): (usize, usize), last: usize| {
+            if options.flipped {
+                (last - x, last - y)
+            } else {
+                (x, y)
+            }
+        };
+        let slot = |pos| turn(pos, last - 1);
+        let fence = |pos| board.fences[slot(pos).1][slot(pos).0];
+
+        Line::edge(glyphs.top, glyphs.line, board.size).write(f, options.color)?;
+
+        for y in 0..=last {
+            let mut row = Line::default();
+            if options.labels == Labels::Off {
+                row.push("   ", false);
+            } else {
+                row.push(&format!("{:>2} ", board.size - turn((0, y), last).1), false);
+            }
+            row.push_char(glyphs.bar, 1, false);
+            for x in 0..=last {
+                let (square_x, square_y) = turn((x, y), last);
+                match board.squares[square_y][square_x] {
+                    Some(player) => {
+                        let letter = player.letter().to_ascii_uppercase();
+                        row.push(&format!(" {letter} "), false);
+                    }
+                    None => row.push("   ", false),
+                }
+                let is_fenced = x != last
+                    && (y < last && fence((x, y)) == Some(Axis::Vertical)
+                        || y > 0 && fence((x, y - 1)) == Some(Axis::Vertical));
+                if is_fenced {
+                    row.push_char(glyphs.fence_bar, 1, true);
+                } else {
+                    row.push_char(glyphs.bar, 1, false);
+                }
+            }
+            row.write(f, options.color)?;
+            if y == last {
+                break;
+            }
+
+            let mut between = Line::default();
+            between.push("   ", false);
+            between.push_char(glyphs.middle[0], 1, false);
+            for x in 0..=last {
+                let is_fenced = x < last && fence((x, y)) == Some(Axis::Horizontal)
+                    || x > 0 && fence((x - 1, y)) == Some(Axis::Horizontal);
+                if is_fenced {
+                    between.push_char(glyphs.fence_line, 3, true);
+                } else if options.labels == Labels::SquaresAndSlots && x < last {
+                    let name = notation::slot_name(slot((x, y)), board.size);
+                    between.push_char(glyphs.line, 3_usize.saturating_sub(name.len()), false);
+                    between.push(&name, false);
+                } else {
+                    between.push_char(glyphs.line, 3, false);
+                }
+                match (x == last, fence((x.min(last - 1), y))) {
+                    (true, _) => between.push_char(glyphs.middle[2], 1, false),
+                    (false, Some(Axis::Vertical)) => between.push_char(glyphs.fence_bar, 1, true),
+                    (false, Some(Axis::Horizontal)) => {
+                        between.push_char(glyphs.fence_line, 1, true);
+                    }
+                    (false, None) => between.push_char(glyphs.middle[1], 1, false),
+                }
+            }
+            between.write(f, options.color)?;
+        }
+
+        Line::edge(glyphs.bottom, glyphs.line, board.size).write(f, options.color)?;
+
+        if options.labels != Labels::Off {
+            let mut files = String::from("   ");
+            for x in 0..=last {
+                files.push_str("  ");
+                files.push(notation::file_char(turn((x, 0), last).0));
+                files.push(' ');
+            }
+            writeln!(f, "{}", files.trim_end())?;
+        }
+        writeln!(f)?;
+        write!(f, "  ")?;
+        for (idx, &player) in board.players.iter().enumerate() {
+            let separator = if idx == 0 {
+                String::new()
+            } else {
+                format!(" {}", glyphs.bar)
+            };
+            write!(
+                f,
+                "{separator} {player:?} - {:>2} fences",
+                board.fences_left(player)
+            )?;
+        }
+        writeln!(f)
+    }
+}
+
+#[test]
+fn draws_with_the_options() {
+    let (board, _) =
+        Board::from_position_str("3x3 b1 a3 a1h 1 1 w").expect("The position is valid");
+    let plain = RenderOptions {
+        charset: Charset::Ascii,
+        color: false,
+        labels: Labels::Off,
+        flipped: false,
+    };
+    assert_eq!(
+        board.render(plain).to_string(),
+        "   +---+---+---+\n   | B |   |   |\n   +---+---+---+\n   |   |   |   |\n   \
+         +=======+---+\n   |   | W |   |\n   +---+---+---+\n\n   \
+         White -  1 fences | Black -  1 fences\n"
+    );
+    let flipped = board.render(RenderOptions {
+        flipped: true,
+        ..plain
+    });
+    let lines: Vec<_> = flipped.to_string().lines().map(String::from).collect();
+    assert_eq!(lines[1], "   |   | W |   |");
+    assert_eq!(lines[2], "   +---+=======+");
+    assert_eq!(lines[5], "   |   |   | B |");
+
+    let fancy = board.to_string();
+    assert!(fancy.contains("\x1b[31m━━━━━━━\x1b[0m"));
+    assert!(fancy.contains(" 1 │   │ W │   │"));
+    assert!(fancy.contains("\n     a   b   c\n"));
+    assert!(!board
+        .render(RenderOptions {
+            color: false,
+            ..RenderOptions::default()
+        })
+        .to_string()
+        .contains('\x1b'));
+}
+
+#[test]
+fn labels_match_the_notation() {
+    let (board, _) =
+        Board::from_position_str("d5 e6 e5h,c3v,e3h 7 9 b").expect("The position is valid");
+    let options = RenderOptions {
+        color: false,
+        labels: Labels::SquaresAndSlots,
+        ..RenderOptions::default()
+    };
+    let lines: Vec<_> = board
+        .render(options)
+        .to_string()
+        .lines()
+        .map(String::from)
+        .collect();
+    assert_eq!(lines[9], " 5 │   │   │   │ W │   │   │   │   │   │");
+    // The fence `e5h` covers the names of its own slot and the one right of it
+    assert_eq!(lines[8], "   ├─a5┼─b5┼─c5┼─d5┼━━━━━━━┼─g5┼─h5┼───┤");
+    assert_eq!(lines[19], "     a   b   c   d   e   f   g   h   i");
+
+    let flipped: Vec<_> = board
+        .render(RenderOptions {
+            flipped: true,
+            ..options
+        })
+        .to_string()
+        .lines()
+        .map(String::from)
+        .collect();
+    assert_eq!(flipped[1], " 1 │   │   │   │   │   │   │   │   │   │");
+    assert_eq!(flipped[2], "   ├─h1┼─g1┼─f1┼─e1┼─d1┼─c1┼─b1┼─a1┼───┤");
+    assert_eq!(flipped[19], "     i   h   g   f   e   d   c   b   a");
+}
@@ -1,12 +1,431 @@
-use rand::seq::SliceRandom;
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
-use super::{Board, LegalMove};
+use super::{
+    bitboard,
+    transposition_table::{Bound, Entry, TranspositionTable},
+    Board, LegalMove,
+};
 use crate::game::PlayerColor;
+
+/// Score of a won position, before it is shortened by the distance to the win
+pub const WIN_SCORE: i32 = 1_000_000;
+/// Scores at least this large in absolute value mean a forced win or loss
+pub const WIN_THRESHOLD: i32 = WIN_SCORE - 1_000;
+pub const DEFAULT_DEPTH: u32 = 2;
+/// Iterative deepening stops here even without any other limit
+pub const MAX_DEPTH: u32 = 64;
+
+/// Value of being one step closer to the goal than the opponent
+const PATH_WEIGHT: i32 = 100;
+/// Value of having one more fence in hand than the opponent
+const FENCE_WEIGHT: i32 = 10;
+/// Value of being the one to move
+const TEMPO: i32 = 5;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult {
+    pub best_move: LegalMove,
+    /// Score from the point of view of the searching player
+    pub score: i32,
+    /// The expected line of play, starting with `best_move`
+    pub principal_variation: Vec<LegalMove>,
+    /// Depth of the last fully completed iteration
+    pub depth: u32,
+    pub nodes: u64,
+}
+
+/// Bounds on how long a search may run, any of which ends it
+#[derive(Debug, Clone, Default)]
+pub struct SearchLimits {
+    pub depth: Option<u32>,
+    pub time: Option<Duration>,
+    pub nodes: Option<u64>,
+    /// Setting this flag from another thread aborts the search
+    pub stop: Option<Arc<AtomicBool>>,
+}
+
+impl SearchLimits {
+    pub const fn depth(depth: u32) -> Self {
+        Self {
+            depth: Some(depth),
+            time: None,
+            nodes: None,
+            stop: None,
+        }
+    }
+
+    pub const fn time(time: Duration) -> Self {
+        Self {
+            depth: None,
+            time: Some(time),
+            nodes: None,
+            stop: None,
+        }
+    }
+}
+
+struct Searcher<'a> {
+    /// The searching player, who plays against everyone else at once in four-player games
+    root: PlayerColor,
+    limits: &'a SearchLimits,
+    table: &'a mut TranspositionTable,
+    deadline: Option<Instant>,
+    nodes: u64,
+    ply: u32,
+    /// Set while the first iteration runs, since it has to produce some move
+    may_abort: bool,
+    aborted: bool,
+}
+
+impl Searcher<'_> {
+    /// Win scores are stored relative to the position they were found in,
+    /// so that they stay correct when the position is reached at another ply
+    const fn score_to_table(&self, score: i32) -> i32 {
+        if score >= WIN_THRESHOLD {
+            score + self.ply.cast_signed()
+        } else if score <= -WIN_THRESHOLD {
+            score - self.ply.cast_signed()
+        } else {
+            score
+        }
+    }
+
+    const fn score_from_table(&self, score: i32) -> i32 {
+        if score >= WIN_THRESHOLD {
+            score - self.ply.cast_signed()
+        } else if score <= -WIN_THRESHOLD {
+            score + self.ply.cast_signed()
+        } else {
+            score
+        }
+    }
+
+    /// Whether `a` and `b` are both the searching player or both among the others
+    fn is_same_side(&self, a: PlayerColor, b: PlayerColor) -> bool {
+        (a == self.root) == (b == self.root)
+    }
+
+    fn should_abort(&self) -> bool {
+        self.limits.nodes.is_some_and(|nodes| self.nodes >= nodes)
+            || self
+                .limits
+                .stop
+                .as_ref()
+                .is_some_and(|stop| stop.load(Ordering::Relaxed))
+            || self
+                .deadline
+                .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    fn negamax(
+        &mut self,
+        board: &mut Board,
+        player: PlayerColor,
+        depth: u32,
+        mut alpha: i32,
+        beta: i32,
+        pv: &mut Vec<LegalMove>,
+    ) -> i32 {
+        self.nodes += 1;
+        pv.clear();
+        if self.may_abort && self.should_abort() {
+            self.aborted = true;
+            return 0;
+        }
+        // The only way for the game to be over is for the previous move to have won it
+        if let Some(winner) = board.is_game_won() {
+            let score = WIN_SCORE - self.ply.cast_signed();
+            return if self.is_same_side(winner, player) {
+                score
+            } else {
+                -score
+            };
+        }
+        if depth == 0 {
+            let standing = board.standing(self.root);
+            return if self.is_same_side(player, self.root) {
+                standing
+            } else {
+                -standing
+            } + TEMPO;
+        }
+
+        let key = board.hash_key(player);
+        let entry = self.table.probe(key);
+        if let Some(entry) = entry.filter(|entry| entry.depth >= depth && self.ply > 0) {
+            let score = self.score_from_table(entry.score);
+            let is_usable = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => score >= beta,
+                Bound::Upper => score <= alpha,
+            };
+            if is_usable {
+                pv.extend(entry.best_move);
+                return score;
+            }
+        }
+
+        let next = board.next_player(player);
+        let mut moves = board.legal_moves(player);
+        if moves.is_empty() {
+            // A pawn boxed in by the others, with no fences left to place, passes
+            return self.search_move(board, player, next, depth, alpha, beta, &mut vec![]);
+        }
+        if let Some(hash_move) = entry.and_then(|entry| entry.best_move) {
+            if let Some(idx) = moves.iter().position(|&r#move| r#move == hash_move) {
+                moves[..=idx].rotate_right(1);
+            }
+        }
+
+        let original_alpha = alpha;
+
+        let mut best_score = -WIN_SCORE;
+        let mut child_pv = vec![];
+        for r#move in moves {
+            board.make_legal_move(r#move, player);
+            let score = self.search_move(board, player, next, depth, alpha, beta, &mut child_pv);
+            board.unmake_legal_move(r#move, player);
+            if self.aborted {
+                return 0;
+            }
+
+            if score > best_score {
+                best_score = score;
+                pv.clear();
+                pv.push(r#move);
+                pv.extend_from_slice(&child_pv);
+            }
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        self.table.store(Entry {
+            key,
+            depth,
+            bound: if best_score <= original_alpha {
+                Bound::Upper
+            } else if best_score >= beta {
+                Bound::Lower
+            } else {
+                Bound::Exact
+            },
+            score: self.score_to_table(best_score),
+            best_move: pv.first().copied(),
+        });
+        best_score
+    }
+}
+
+impl Searcher<'_> {
+    /// Searches the position after `player` moved or passed, `next` being the one to move
+    #[allow(clippy::too_many_arguments)]
+    fn search_move(
+        &mut self,
+        board: &mut Board,
+        player: PlayerColor,
+        next: PlayerColor,
+        depth: u32,
+        alpha: i32,
+        beta: i32,
+        pv: &mut Vec<LegalMove>,
+    ) -> i32 {
+        self.ply += 1;
+        // Consecutive moves by the same side, as happen between the others in four-player
+        // games, keep the point of view of the score
+        let score = if self.is_same_side(player, next) {
+            self.negamax(board, next, depth - 1, alpha, beta, pv)
+        } else {
+            -self.negamax(board, next, depth - 1, -beta, -alpha, pv)
+        };
+        self.ply -= 1;
+        score
+    }
+}
+
 impl Board {
     pub fn find_best_move(&self, player: PlayerColor) -> LegalMove {
-        self.legal_moves(player)
-            .choose(&mut rand::thread_rng())
+        self.search(player, DEFAULT_DEPTH).best_move
+    }
+
+    /// Searches `depth` plies ahead using negamax with alpha-beta pruning
+    pub fn search(&self, player: PlayerColor, depth: u32) -> SearchResult {
+        self.search_with_limits(player, &SearchLimits::depth(depth))
+    }
+
+    /// Searches with iterative deepening until one of the `limits` is hit,
+    /// returning the result of the deepest fully completed iteration
+    ///
+    /// The first iteration always runs to completion, so that there is a move to return,
+    /// even if the depth limit is 0
+    pub fn search_with_limits(&self, player: PlayerColor, limits: &SearchLimits) -> SearchResult {
+        self.search_with_table(player, limits, &mut TranspositionTable::default())
+    }
+
+    /// Like `search_with_limits`, but reuses `table`, which may hold results of earlier searches
+    ///
+    /// In four-player games the scores in the table depend on who is searching, so a table
+    /// shouldn't be shared between players
+    pub fn search_with_table(
+        &self,
+        player: PlayerColor,
+        limits: &SearchLimits,
+        table: &mut TranspositionTable,
+    ) -> SearchResult {
+        let start = Instant::now();
+        let mut board = self.clone();
+        let mut searcher = Searcher {
+            root: player,
+            limits,
+            table,
+            deadline: limits.time.map(|time| start + time),
+            nodes: 0,
+            ply: 0,
+            may_abort: false,
+            aborted: false,
+        };
+        let mut result = None;
+        for depth in 1..=limits.depth.unwrap_or(MAX_DEPTH).max(1) {
+            let mut principal_variation = vec![];
+            let score = searcher.negamax(
+                &mut board,
+                player,
+                depth,
+                -WIN_SCORE,
+                WIN_SCORE,
+                &mut principal_variation,
+            );
+            if searcher.aborted {
+                break;
+            }
+            searcher.may_abort = true;
+            result = Some(SearchResult {
+                best_move: *principal_variation
+                    .first()
+                    .expect("Only players who can move are searched for"),
+                score,
+                principal_variation,
+                depth,
+                nodes: searcher.nodes,
+            });
+
+            // The next iteration would take several times as long as all the previous ones,
+            // so it is unlikely to finish in the remaining half of the time budget
+            let out_of_time = limits.time.is_some_and(|time| start.elapsed() * 2 >= time);
+            if score.abs() >= WIN_THRESHOLD || out_of_time || searcher.should_abort() {
+                break;
+            }
+        }
+        let mut result = result.expect("The first iteration can't be aborted");
+        result.nodes = searcher.nodes;
+        result
+    }
+
+    /// Scores the position for `player`, assuming it is their turn to move
+    ///
+    /// The score is mostly the difference between the lengths of the shortest paths of
+    /// the pawns, with the fences left in hand used to break ties
+    pub fn evaluate(&self, player: PlayerColor) -> i32 {
+        self.standing(player) + TEMPO
+    }
+
+    /// How much better off `player` is than the others, not counting whose turn it is
+    ///
+    /// With several opponents, `player` is compared to the closest one to their goal and to the
+    /// most fences any of them have left.
+    fn standing(&self, player: PlayerColor) -> i32 {
+        let distance = |player| {
+            self.shortest_path_len(player)
+                .expect("There should always be a path for every pawn")
+                .cast_signed()
+        };
+        let fences = |player| self.fences_left(player).cast_signed();
+        let opponents = self
+            .players
+            .iter()
             .copied()
-            .expect("A player should always be able to make a move")
+            .filter(|&other| other != player);
+        let opponent_distance = opponents
+            .clone()
+            .map(distance)
+            .min()
+            .expect("There is always an opponent");
+        let opponent_fences = opponents
+            .map(fences)
+            .max()
+            .expect("There is always an opponent");
+        (opponent_distance - distance(player)) * PATH_WEIGHT
+            + (fences(player) - opponent_fences) * FENCE_WEIGHT
     }
+
+    /// Number of pawn steps `player` needs to reach their goal row, ignoring the other pawn
+    pub const fn shortest_path_len(&self, player: PlayerColor) -> Option<u32> {
+        self.walls
+            .distance(bitboard::bit(self.pawn_pos(player)), self.goal(player))
+    }
+}
+
+#[test]
+fn takes_the_winning_move() {
+    let mut board = Board::default();
+    board.move_pawn_unchecked(PlayerColor::White, (4, 8), (2, 1));
+    let result = board.search(PlayerColor::White, 3);
+    assert_eq!(
+        result.best_move,
+        LegalMove(super::Lmi::MovePlayer((2, 1), (2, 0)), 9)
+    );
+    assert!(result.score >= WIN_THRESHOLD);
+}
+
+#[test]
+fn starting_position_is_balanced() {
+    let board = Board::default();
+    assert_eq!(board.shortest_path_len(PlayerColor::White), Some(8));
+    assert_eq!(board.evaluate(PlayerColor::White), TEMPO);
+    assert_eq!(board.evaluate(PlayerColor::Black), TEMPO);
+}
+
+#[test]
+fn stop_flag_keeps_the_first_iteration() {
+    let limits = SearchLimits {
+        stop: Some(Arc::new(AtomicBool::new(true))),
+        ..SearchLimits::default()
+    };
+    let result = Board::default().search_with_limits(PlayerColor::White, &limits);
+    assert_eq!(result.depth, 1);
+}
+
+#[test]
+fn depth_limit_of_zero_searches_one_ply() {
+    let result = Board::default().search_with_limits(PlayerColor::White, &SearchLimits::depth(0));
+    assert_eq!(result.depth, 1);
+}
+
+#[test]
+fn hash_key_is_restored_by_unmaking() {
+    let mut board = Board::default();
+    let start_key = board.hash_key(PlayerColor::White);
+    for r#move in board.legal_moves(PlayerColor::White) {
+        board.make_legal_move(r#move, PlayerColor::White);
+        assert_eq!(board.hash, board.compute_hash());
+        assert_ne!(board.hash_key(PlayerColor::Black), start_key);
+        board.unmake_legal_move(r#move, PlayerColor::White);
+        assert_eq!(board.hash_key(PlayerColor::White), start_key);
+    }
+}
+
+#[test]
+fn boxed_in_players_pass() {
+    let board = super::invariants::boxed_in_white();
+    let result = board.search(PlayerColor::Green, 3);
+    assert!(board
+        .legal_moves(PlayerColor::Green)
+        .contains(&result.best_move));
 }
@@ -0,0 +1,125 @@
+//! Shortest paths of the pawns, used to skip most of the path checks when generating fences
+//!
+//! A fence that doesn't block any step of a pawn's shortest path leaves that path intact, so it
+//! can't cut the pawn off from its goal. Only the few fences that do block a step need a full
+//! reachability check.
+use super::super::{Axis, PlayerColor};
+use super::bitboard::{bit, Walls, STRIDE};
+use super::{fence_move::Fail, Board};
+
+/// The steps of a path, as the squares they start from in the same form as the wall bitboards
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(super) struct PathSteps {
+    /// Steps between a square and the one right of it
+    right: u128,
+    /// Steps between a square and the one below it
+    down: u128,
+}
+
+impl PathSteps {
+    const fn is_cut_by(self, axis: Axis, (x, y): (usize, usize)) -> bool {
+        match axis {
+            Axis::Horizontal => self.down & (bit((x, y)) | bit((x + 1, y))) != 0,
+            Axis::Vertical => self.right & (bit((x, y)) | bit((x, y + 1))) != 0,
+        }
+    }
+}
+
+impl Walls {
+    /// A shortest path from the square `from` to any of the `goal` squares
+    fn shortest_path(self, from: (usize, usize), goal: u128) -> Option<PathSteps> {
+        // layers[d] holds every square reachable in at most d steps
+        let mut layers = vec![bit(from)];
+        let mut reach = bit(from);
+        while reach & goal == 0 {
+            let next = self.expand(reach);
+            if next == reach {
+                return None;
+            }
+            reach = next;
+            layers.push(reach);
+        }
+
+        let mut steps = PathSteps::default();
+        let mut square = (reach & goal).trailing_zeros() as usize;
+        for layer in layers.iter().rev().skip(1) {
+            let (x, y) = (square % STRIDE, square / STRIDE);
+            if x > 0 && layer & bit((x - 1, y)) != 0 && !self.is_blocked_right(square - 1) {
+                steps.right |= 1 << (square - 1);
+                square -= 1;
+            } else if x + 1 < STRIDE
+                && layer & bit((x + 1, y)) != 0
+                && !self.is_blocked_right(square)
+            {
+                steps.right |= 1 << square;
+                square += 1;
+            } else if y > 0
+                && layer & bit((x, y - 1)) != 0
+                && !self.is_blocked_down(square - STRIDE)
+            {
+                steps.down |= 1 << (square - STRIDE);
+                square -= STRIDE;
+            } else {
+                steps.down |= 1 << square;
+                square += STRIDE;
+            }
+        }
+        Some(steps)
+    }
+}
+
+/// The shortest paths of all pawns, indexed by player, valid until a fence is placed or a pawn
+/// moves
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct ShortestPaths([PathSteps; 4]);
+
+impl Board {
+    pub(super) fn shortest_paths(&self) -> ShortestPaths {
+        let mut paths = ShortestPaths([PathSteps::default(); 4]);
+        for &player in self.players {
+            paths.0[player as usize] = self
+                .walls
+                .shortest_path(self.pawn_pos(player), self.goal(player))
+                .expect("There should always be a path for every pawn");
+        }
+        paths
+    }
+
+    /// Same as `is_fence_move_legal`, but only searches for paths when the fence cuts one of
+    /// the `paths`, which have to be those of this board
+    pub(super) fn is_fence_move_legal_with_paths(
+        &self,
+        player: PlayerColor,
+        axis: Axis,
+        pos: (usize, usize),
+        paths: &ShortestPaths,
+    ) -> Result<(), Fail> {
+        self.can_fence_be_placed(player, axis, pos)?;
+        let walls = self.walls.toggle_fence(axis, pos);
+        for &pawn in self.players {
+            if paths.0[pawn as usize].is_cut_by(axis, pos) && !self.can_pawn_reach_goal(walls, pawn)
+            {
+                return Err(Fail::NoPathRemaining);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn agrees_with_full_path_checks() {
+    super::invariants::for_random_positions(|board, turn, _| {
+        let paths = board.shortest_paths();
+        let slots = board.size - 1;
+        for y in 0..slots {
+            for x in 0..slots {
+                for axis in [Axis::Horizontal, Axis::Vertical] {
+                    assert_eq!(
+                        board.is_fence_move_legal_with_paths(turn, axis, (x, y), &paths),
+                        board.is_fence_move_legal(turn, axis, (x, y))
+                    );
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,63 @@
+use super::LegalMove;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    /// The score is exact
+    Exact,
+    /// The search failed high, so the real score is at least this
+    Lower,
+    /// The search failed low, so the real score is at most this
+    Upper,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entry {
+    pub key: u64,
+    pub depth: u32,
+    pub bound: Bound,
+    pub score: i32,
+    pub best_move: Option<LegalMove>,
+}
+
+/// A fixed-size hash table of search results, indexed by `Board::hash_key`
+#[derive(Debug, Clone)]
+pub struct TranspositionTable {
+    entries: Vec<Option<Entry>>,
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        Self::new(1 << 16)
+    }
+}
+
+impl TranspositionTable {
+    /// Creates a table holding `capacity` entries, rounded up to a power of two
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: vec![None; capacity.max(1).next_power_of_two()],
+        }
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    const fn index(&self, key: u64) -> usize {
+        key as usize & (self.entries.len() - 1)
+    }
+
+    pub fn probe(&self, key: u64) -> Option<Entry> {
+        self.entries[self.index(key)].filter(|entry| entry.key == key)
+    }
+
+    /// Stores the entry, unless its slot holds a deeper search of the same position
+    pub fn store(&mut self, entry: Entry) {
+        let index = self.index(entry.key);
+        let slot = &mut self.entries[index];
+        if slot.is_none_or(|old| old.key != entry.key || old.depth <= entry.depth) {
+            *slot = Some(entry);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.fill(None);
+    }
+}
@@ -0,0 +1,74 @@
+use super::super::{Axis, PlayerColor};
+use super::{MAX_FENCES, MAX_SIZE};
+
+pub(super) struct Keys {
+    pawns: [[[u64; MAX_SIZE]; MAX_SIZE]; 4],
+    fences: [[[u64; MAX_SIZE - 1]; MAX_SIZE - 1]; 2],
+    fences_left: [[u64; MAX_FENCES as usize + 1]; 4],
+    /// Keys of the players to move, where White's is 0 so that it leaves the hash alone
+    to_move: [u64; 4],
+}
+
+/// One step of the `SplitMix64` generator, good enough to fill the key tables at compile time
+const fn split_mix(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+impl Keys {
+    const fn generate() -> Self {
+        let mut state = 0x636f_7272_6964_6f72;
+        let mut keys = Self {
+            pawns: [[[0; MAX_SIZE]; MAX_SIZE]; 4],
+            fences: [[[0; MAX_SIZE - 1]; MAX_SIZE - 1]; 2],
+            fences_left: [[0; MAX_FENCES as usize + 1]; 4],
+            to_move: [0; 4],
+        };
+        let mut c = 0;
+        while c < 4 {
+            let mut y = 0;
+            while y < MAX_SIZE {
+                let mut x = 0;
+                while x < MAX_SIZE {
+                    keys.pawns[c][y][x] = split_mix(&mut state);
+                    if c < 2 && x < MAX_SIZE - 1 && y < MAX_SIZE - 1 {
+                        keys.fences[c][y][x] = split_mix(&mut state);
+                    }
+                    x += 1;
+                }
+                y += 1;
+            }
+            let mut n = 0;
+            while n <= MAX_FENCES as usize {
+                keys.fences_left[c][n] = split_mix(&mut state);
+                n += 1;
+            }
+            if c > 0 {
+                keys.to_move[c] = split_mix(&mut state);
+            }
+            c += 1;
+        }
+        keys
+    }
+
+    pub(super) const fn pawn(&self, player: PlayerColor, (x, y): (usize, usize)) -> u64 {
+        self.pawns[player as usize][y][x]
+    }
+
+    pub(super) const fn fence(&self, axis: Axis, (x, y): (usize, usize)) -> u64 {
+        self.fences[axis as usize][y][x]
+    }
+
+    pub(super) const fn fences_left(&self, player: PlayerColor, count: u32) -> u64 {
+        self.fences_left[player as usize][count as usize]
+    }
+
+    pub(super) const fn to_move(&self, player: PlayerColor) -> u64 {
+        self.to_move[player as usize]
+    }
+}
+
+pub(super) static KEYS: Keys = Keys::generate();
@@ -0,0 +1,136 @@
+//! Chess clocks for timed games, where running out of time loses the game
+//!
+//! Each player has their own time control, made of a base time and what they get back after
+//! every move: nothing for sudden death, a fixed increment with Fischer timing, or the time
+//! they used up to a fixed delay with Bronstein timing.
+use core::fmt::Display;
+use std::time::Duration;
+
+use super::PlayerColor;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Increment {
+    SuddenDeath,
+    Fischer(Duration),
+    Bronstein(Duration),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeControl {
+    pub base: Duration,
+    pub increment: Increment,
+}
+
+/// Written like `5+3` for Fischer timing, `5d3` for Bronstein timing or `5` for sudden death,
+/// with the base time in minutes and the increment or delay in seconds
+impl Display for TimeControl {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{}", self.base.as_secs_f64() / 60.0)?;
+        match self.increment {
+            Increment::SuddenDeath => Ok(()),
+            Increment::Fischer(increment) => write!(f, "+{}", increment.as_secs_f64()),
+            Increment::Bronstein(delay) => write!(f, "d{}", delay.as_secs_f64()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct PlayerClock {
+    control: TimeControl,
+    remaining: Duration,
+}
+
+impl PlayerClock {
+    const fn new(control: TimeControl) -> Self {
+        Self {
+            control,
+            remaining: control.base,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Clock {
+    /// Indexed like `PlayerColor::ALL`
+    players: Vec<PlayerClock>,
+}
+
+impl Clock {
+    /// A clock with the time controls of the players in the order of `PlayerColor::ALL`, one for
+    /// each player taking part
+    pub fn new(controls: &[TimeControl]) -> Self {
+        Self {
+            players: controls.iter().copied().map(PlayerClock::new).collect(),
+        }
+    }
+
+    fn player(&self, player: PlayerColor) -> &PlayerClock {
+        &self.players[player as usize]
+    }
+
+    pub fn control(&self, player: PlayerColor) -> TimeControl {
+        self.player(player).control
+    }
+
+    pub fn remaining(&self, player: PlayerColor) -> Duration {
+        self.player(player).remaining
+    }
+
+    /// Charges `player` for a move that took `elapsed`, returning whether they made it in time
+    #[must_use]
+    pub fn charge(&mut self, player: PlayerColor, elapsed: Duration) -> bool {
+        let clock = &mut self.players[player as usize];
+        if elapsed >= clock.remaining {
+            clock.remaining = Duration::ZERO;
+            return false;
+        }
+        clock.remaining -= elapsed;
+        clock.remaining += match clock.control.increment {
+            Increment::SuddenDeath => Duration::ZERO,
+            Increment::Fischer(increment) => increment,
+            Increment::Bronstein(delay) => elapsed.min(delay),
+        };
+        true
+    }
+
+    /// How long a bot playing `player` can think about its next move, a small share of its time
+    /// plus what it gets back after the move, but never enough to risk running out
+    pub fn move_budget(&self, player: PlayerColor) -> Duration {
+        let clock = self.player(player);
+        let refund = match clock.control.increment {
+            Increment::SuddenDeath => Duration::ZERO,
+            Increment::Fischer(refund) | Increment::Bronstein(refund) => refund,
+        };
+        (clock.remaining / 30 + refund).min(clock.remaining / 2)
+    }
+}
+
+/// Remaining time as `m:ss.t`
+pub fn format_time(time: Duration) -> String {
+    let tenths = time.as_millis() / 100;
+    format!("{}:{:02}.{}", tenths / 600, tenths / 10 % 60, tenths % 10)
+}
+
+#[test]
+fn increments_are_given_back() {
+    let minute = |increment| TimeControl {
+        base: Duration::from_mins(1),
+        increment,
+    };
+    let mut clock = Clock::new(&[
+        minute(Increment::Fischer(Duration::from_secs(2))),
+        minute(Increment::Bronstein(Duration::from_secs(2))),
+    ]);
+    assert!(clock.charge(PlayerColor::White, Duration::from_secs(5)));
+    assert_eq!(clock.remaining(PlayerColor::White), Duration::from_secs(57));
+    assert!(clock.charge(PlayerColor::Black, Duration::from_secs(5)));
+    assert_eq!(clock.remaining(PlayerColor::Black), Duration::from_secs(57));
+    assert!(clock.charge(PlayerColor::Black, Duration::from_secs(1)));
+    assert_eq!(clock.remaining(PlayerColor::Black), Duration::from_secs(57));
+
+    let mut clock = Clock::new(&[minute(Increment::SuddenDeath); 2]);
+    assert!(clock.charge(PlayerColor::White, Duration::from_secs(59)));
+    assert!(!clock.charge(PlayerColor::White, Duration::from_secs(1)));
+    assert_eq!(clock.remaining(PlayerColor::White), Duration::ZERO);
+    assert_eq!(format_time(Duration::from_millis(83_450)), "1:23.4");
+}
@@ -0,0 +1,193 @@
+//! The widely used Quoridor notation, where columns are the letters `a` to `i` from left to right
+//! and rows are the numbers `1` to `9` from White's side of the board
+//!
+//! A pawn move is written as its destination square, like `e2`. A fence is written as the square
+//! to the lower left of its center, followed by `h` or `v` for its axis, like `e3h`. Other board
+//! sizes use as many letters and numbers as they have columns and rows, like `k11` on 11x11.
+use core::fmt::Display;
+
+use super::{Axis, LegalMove, Move, MoveChars, TryIntoMoveError};
+
+/// Name of the square at `(x, y)` on a board of `size`
+pub fn square_name((x, y): (usize, usize), size: usize) -> String {
+    format!("{}{}", file_char(x), size - y)
+}
+
+/// Name of the fence in the slot at `(x, y)` on a board of `size`
+pub fn fence_name(axis: Axis, pos: (usize, usize), size: usize) -> String {
+    let axis = match axis {
+        Axis::Horizontal => 'h',
+        Axis::Vertical => 'v',
+    };
+    format!("{}{axis}", slot_name(pos, size))
+}
+
+/// Name of the fence slot at `(x, y)` on a board of `size`, which is that of the square to the
+/// lower left of its center
+pub fn slot_name((x, y): (usize, usize), size: usize) -> String {
+    square_name((x, y + 1), size)
+}
+
+#[allow(clippy::cast_possible_truncation)]
+pub const fn file_char(x: usize) -> char {
+    (b'a' + x as u8) as char
+}
+
+/// Parses a move in the standard notation on a board of `size`, like `e2` or `e3h`
+pub fn parse_move(value: &str, size: usize) -> Result<Move, TryIntoMoveError> {
+    let mut chars = MoveChars::new(value);
+    // Fences end with their axis and can't be on the last column or row, so those are only
+    // allowed for pawn moves
+    if !value.chars().last().is_some_and(char::is_alphabetic) {
+        let x = chars.parse_file("a column", size)?;
+        let y = chars.parse_rank("a row", size)?;
+        chars.end()?;
+        return Ok(Move::PawnTo((x, size - 1 - y)));
+    }
+    let x = chars.parse_file("a fence column", size - 1)?;
+    let y = chars.parse_rank("a fence row", size - 1)?;
+    let axis = chars.parse("a fence axis (`h` or `v`)", |c| match c {
+        'h' => Some(Axis::Horizontal),
+        'v' => Some(Axis::Vertical),
+        _ => None,
+    })?;
+    chars.end()?;
+    Ok(Move::PlaceFence(axis, (x, size - 2 - y)))
+}
+
+impl Display for LegalMove {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let size = self.board_size();
+        match (self.destination(), self.fence()) {
+            (Some(pos), _) => write!(f, "{}", square_name(pos, size)),
+            (_, Some((axis, pos))) => write!(f, "{}", fence_name(axis, pos, size)),
+            (None, None) => unreachable!("A move either moves the pawn or places a fence"),
+        }
+    }
+}
+
+#[test]
+fn round_trips_through_legal_moves() {
+    use super::{Board, PlayerColor};
+    let board = Board::default();
+    for r#move in board.legal_moves(PlayerColor::White) {
+        let parsed = Move::try_from(r#move.to_string()).expect("Printed moves should parse");
+        assert_eq!(
+            board.make_move_legal(parsed, PlayerColor::White),
+            Ok(r#move)
+        );
+    }
+    assert_eq!(
+        Move::try_from("e3h".to_owned()),
+        Ok(Move::PlaceFence(Axis::Horizontal, (4, 5)))
+    );
+    assert_eq!(Move::try_from("e2".to_owned()), Ok(Move::PawnTo((4, 7))));
+}
+
+#[test]
+fn custom_fence_rows_count_from_the_top() {
+    for (custom, standard) in [("-e6", "e3h"), ("|a1", "a8v"), ("-h8", "h1h")] {
+        assert_eq!(
+            Move::parse(custom, 9),
+            Move::parse(standard, 9),
+            "`{custom}`"
+        );
+    }
+    assert_eq!(Move::parse("-b4", 5), Move::parse("b1h", 5));
+}
+
+#[test]
+fn parse_errors_point_at_the_character() {
+    let error = |value: &str| Move::try_from(value.to_owned()).expect_err("The move is invalid");
+    assert_eq!(
+        error("e0"),
+        TryIntoMoveError::UnexpectedChar {
+            index: 1,
+            found: '0',
+            expected: "a row from `1` to `9`".to_owned()
+        }
+    );
+    assert_eq!(
+        error("i9h"),
+        TryIntoMoveError::UnexpectedChar {
+            index: 0,
+            found: 'i',
+            expected: "a fence column from `a` to `h`".to_owned()
+        }
+    );
+    assert_eq!(
+        error("e3hv"),
+        TryIntoMoveError::UnexpectedChar {
+            index: 3,
+            found: 'v',
+            expected: "the end of the move".to_owned()
+        }
+    );
+    assert_eq!(
+        error("-i9"),
+        TryIntoMoveError::UnexpectedChar {
+            index: 1,
+            found: 'i',
+            expected: "a fence column from `a` to `h`".to_owned()
+        }
+    );
+    assert_eq!(
+        error("-\u{e9}1"),
+        TryIntoMoveError::UnexpectedChar {
+            index: 1,
+            found: '\u{e9}',
+            expected: "a fence column from `a` to `h`".to_owned()
+        }
+    );
+    assert_eq!(
+        error("-a"),
+        TryIntoMoveError::UnexpectedEnd {
+            expected: "a fence row from `1` to `8`".to_owned()
+        }
+    );
+    assert_eq!(
+        error("wx").to_string(),
+        "unexpected `x` at character 2, expected a direction (`w`, `a`, `s` or `d`)"
+    );
+    assert_eq!(
+        Move::try_from("-h8".to_owned()),
+        Ok(Move::PlaceFence(Axis::Horizontal, (7, 7)))
+    );
+}
+
+#[test]
+fn adapts_to_the_board_size() {
+    assert_eq!(Move::parse("k11", 11), Ok(Move::PawnTo((10, 0))));
+    assert_eq!(
+        Move::parse("j10v", 11),
+        Ok(Move::PlaceFence(Axis::Vertical, (9, 0)))
+    );
+    assert_eq!(Move::parse("c1", 5), Ok(Move::PawnTo((2, 4))));
+    assert_eq!(
+        Move::parse("e5h", 5),
+        Err(TryIntoMoveError::UnexpectedChar {
+            index: 0,
+            found: 'e',
+            expected: "a fence column from `a` to `d`".to_owned()
+        })
+    );
+    assert_eq!(
+        Move::parse("f1", 5),
+        Err(TryIntoMoveError::UnexpectedChar {
+            index: 0,
+            found: 'f',
+            expected: "a column from `a` to `e`".to_owned()
+        })
+    );
+    assert_eq!(
+        Move::parse("a10", 9),
+        Err(TryIntoMoveError::UnexpectedChar {
+            index: 2,
+            found: '0',
+            expected: "the end of the move".to_owned()
+        })
+    );
+    assert_eq!(square_name((10, 0), 11), "k11");
+    assert_eq!(fence_name(Axis::Horizontal, (0, 3), 5), "a1h");
+    assert_eq!(slot_name((9, 0), 11), "j10");
+}
@@ -0,0 +1,469 @@
+//! Game records, a list of headers followed by the moves in the standard notation:
+//!
+//! ```text
+//! [White "human"]
+//! [Black "ab:depth=2"]
+//! [Date "2026.10.18"]
+//! [Fences "10"]
+//! [Result "1-0"]
+//! [Position "e1 e9 - 10 10 w"]
+//!
+//! 1. e2 e8 2. e3 e3h
+//! ```
+//!
+//! Games on other board sizes also have a `Size` header, like `[Size "7"]`. Four-player games
+//! have a `[Players "4"]` header, `Red` and `Green` headers for the other two players and four
+//! moves to each move number, and their `Result` names the winner, like `red`.
+use core::fmt::Display;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{
+    board::{position::PositionError, STANDARD_SIZE},
+    state::GameResult,
+    Board, BoardConfig, LegalMove, Move, MoveMakeFail, PlayerColor, TryIntoMoveError,
+};
+
+const RESULT_TOKENS: [&str; 4] = ["1-0", "0-1", "1/2-1/2", "*"];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GameRecord {
+    pub headers: Vec<(String, String)>,
+    /// The moves as they are written, which are only checked when replaying
+    pub moves: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// The header on this line (counting from 1) isn't of the form `[Name "value"]`
+    MalformedHeader(usize),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IllegalMoveReason {
+    /// The starting position given in the headers is invalid, so no move could be played
+    InvalidPosition(PositionError),
+    Unparsable(TryIntoMoveError),
+    Illegal(MoveMakeFail),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplayError {
+    /// Index of the offending move among the moves of the record
+    pub ply: usize,
+    /// The number the offending move is written under, as given by `number_moves`
+    pub move_number: usize,
+    /// Whether the offending move was made by someone other than the first player in turn
+    /// order, so that it's written after `...`
+    pub continued: bool,
+    pub text: String,
+    pub reason: IllegalMoveReason,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::MalformedHeader(line) => write!(f, "malformed header on line {line}"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl Display for ReplayError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let kind = match &self.reason {
+            IllegalMoveReason::InvalidPosition(err) => {
+                return write!(f, "invalid starting position: {err}");
+            }
+            IllegalMoveReason::Unparsable(_) => "unparsable",
+            IllegalMoveReason::Illegal(_) => "illegal",
+        };
+        write!(
+            f,
+            "move {}{} `{}` is {kind}",
+            self.move_number,
+            if self.continued { "..." } else { "." },
+            self.text
+        )?;
+        match &self.reason {
+            IllegalMoveReason::Unparsable(err) => write!(f, ": {err}")?,
+            IllegalMoveReason::Illegal(err) => write!(f, ": {err}")?,
+            IllegalMoveReason::InvalidPosition(_) => {}
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl GameRecord {
+    /// A record with the headers every game has, filled in with today's date, where `names`
+    /// are those of the players in the order of `PlayerColor::ALL`
+    pub fn new(names: &[&str], config: BoardConfig) -> Self {
+        let mut record = Self::default();
+        for (player, name) in PlayerColor::ALL.iter().zip(names) {
+            record.set_header(&format!("{player:?}"), name);
+        }
+        record.set_header("Date", &today());
+        if config.size != STANDARD_SIZE {
+            record.set_header("Size", &config.size.to_string());
+        }
+        if config.players != 2 {
+            record.set_header("Players", &config.players.to_string());
+        }
+        record.set_header("Fences", &config.fences.to_string());
+        record.set_header("Result", "*");
+        record
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn set_header(&mut self, name: &str, value: &str) {
+        match self.headers.iter_mut().find(|(header, _)| header == name) {
+            Some((_, old)) => value.clone_into(old),
+            None => self.headers.push((name.to_owned(), value.to_owned())),
+        }
+    }
+
+    pub fn push(&mut self, r#move: LegalMove) {
+        self.moves.push(r#move.to_string());
+    }
+
+    /// Number of players in the game, 2 unless the `Players` header says otherwise
+    pub fn players(&self) -> usize {
+        self.header("Players")
+            .and_then(|players| players.parse().ok())
+            .filter(|&players| BoardConfig::is_valid_player_count(players))
+            .unwrap_or(2)
+    }
+
+    pub fn set_result(&mut self, result: GameResult) {
+        if self.players() != 2 {
+            let result = match result {
+                GameResult::Win(winner) => winner.to_string(),
+                GameResult::Draw(_) => "1/2-1/2".to_owned(),
+                // Nobody wins when one of several players runs out of time
+                GameResult::OutOfTime(_) => "*".to_owned(),
+            };
+            self.set_header("Result", &result);
+            return;
+        }
+        let result = match result {
+            GameResult::Win(PlayerColor::White) | GameResult::OutOfTime(PlayerColor::Black) => {
+                "1-0"
+            }
+            GameResult::Win(PlayerColor::Black) | GameResult::OutOfTime(PlayerColor::White) => {
+                "0-1"
+            }
+            GameResult::Draw(_) => "1/2-1/2",
+            GameResult::Win(PlayerColor::Red | PlayerColor::Green)
+            | GameResult::OutOfTime(PlayerColor::Red | PlayerColor::Green) => {
+                unreachable!("Red and Green only play in four-player games")
+            }
+        };
+        self.set_header("Result", result);
+    }
+
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut record = Self::default();
+        let mut lines = text.lines().enumerate().peekable();
+        while let Some((idx, line)) = lines.next_if(|(_, line)| {
+            let line = line.trim();
+            line.is_empty() || line.starts_with('[')
+        }) {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (name, value) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+                .and_then(|line| line.split_once(' '))
+                .and_then(|(name, value)| {
+                    Some((name, value.trim().strip_prefix('"')?.strip_suffix('"')?))
+                })
+                .ok_or(ParseError::MalformedHeader(idx + 1))?;
+            record.set_header(name, value);
+        }
+        record.moves = lines
+            .flat_map(|(_, line)| line.split_whitespace())
+            .filter(|token| !token.ends_with('.') && !RESULT_TOKENS.contains(token))
+            .map(String::from)
+            .collect();
+        Ok(record)
+    }
+
+    /// The position the game started from, given by the `Position` header if there is one
+    pub fn starting_position(&self) -> Result<(Board, PlayerColor), PositionError> {
+        if let Some(position) = self.header("Position") {
+            return Board::from_position_str(position);
+        }
+        let defaults = BoardConfig::with_players(self.players());
+        let size = self
+            .header("Size")
+            .and_then(|size| size.parse().ok())
+            .filter(|&size| BoardConfig::is_valid_size(size))
+            .unwrap_or(defaults.size);
+        let fences = match self.header("Fences") {
+            Some(fences) => fences
+                .parse()
+                .ok()
+                .filter(|&fences| BoardConfig::is_valid_fence_count(fences))
+                .ok_or_else(|| PositionError::InvalidFenceCount(fences.to_owned()))?,
+            None => defaults.fences,
+        };
+        Ok((
+            Board::new(BoardConfig {
+                size,
+                fences,
+                players: defaults.players,
+            }),
+            PlayerColor::White,
+        ))
+    }
+
+    /// Plays the moves from the starting position, stopping at the first illegal one, where
+    /// players who can't move are skipped like in a `Game`
+    pub fn replay(&self) -> Result<Board, ReplayError> {
+        let (mut board, turn) = self.starting_position().map_err(|err| ReplayError {
+            ply: 0,
+            move_number: 1,
+            continued: false,
+            text: String::new(),
+            reason: IllegalMoveReason::InvalidPosition(err),
+        })?;
+        let (movers, illegal) = self.play_moves(&mut board, turn);
+        let Some((ply, reason)) = illegal else {
+            return Ok(board);
+        };
+        Err(ReplayError {
+            ply,
+            move_number: number_moves(&board, &movers)[ply],
+            continued: board.turn_index(movers[ply]) != 0,
+            text: self.moves[ply].clone(),
+            reason,
+        })
+    }
+
+    /// Plays the moves on `board`, starting with `turn` or the next player who can move, as
+    /// far as they are legal. Returns who made each of the moves, where those after an illegal
+    /// one are taken to follow the turn order, and the first illegal move.
+    fn play_moves(
+        &self,
+        board: &mut Board,
+        turn: PlayerColor,
+    ) -> (Vec<PlayerColor>, Option<(usize, IllegalMoveReason)>) {
+        let mut turn = board.first_able_to_move(turn);
+        let mut movers = Vec::with_capacity(self.moves.len());
+        let mut illegal = None;
+        for (ply, text) in self.moves.iter().enumerate() {
+            movers.push(turn);
+            if illegal.is_none() {
+                let r#move = Move::parse(text, board.size())
+                    .map_err(IllegalMoveReason::Unparsable)
+                    .and_then(|r#move| {
+                        board
+                            .make_move_legal(r#move, turn)
+                            .map_err(IllegalMoveReason::Illegal)
+                    });
+                match r#move {
+                    Ok(r#move) => {
+                        board.make_legal_move(r#move, turn);
+                        turn = board.next_player_to_move(turn);
+                        continue;
+                    }
+                    Err(reason) => illegal = Some((ply, reason)),
+                }
+            }
+            turn = board.next_player(turn);
+        }
+        (movers, illegal)
+    }
+}
+
+/// The number of each move made by `movers` in turn, counting from 1 and going up whenever the
+/// turn order on `board` starts over, so that players who can't move and are skipped don't
+/// shift the numbers of the moves after them
+pub fn number_moves(board: &Board, movers: &[PlayerColor]) -> Vec<usize> {
+    let mut number = 1;
+    let mut last = None;
+    movers
+        .iter()
+        .map(|&mover| {
+            let idx = board.turn_index(mover);
+            if last.is_some_and(|last| idx <= last) {
+                number += 1;
+            }
+            last = Some(idx);
+            number
+        })
+        .collect()
+}
+
+impl Display for GameRecord {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        for (name, value) in &self.headers {
+            writeln!(f, "[{name} \"{value}\"]")?;
+        }
+        writeln!(f)?;
+        // Move numbers go up with the first player in turn order, so when someone else starts
+        // one, like at the start or after that player is skipped, it's written like `1... e8`
+        let (mut board, turn) = self.starting_position().unwrap_or_else(|_| {
+            let board = Board::new(BoardConfig::with_players(self.players()));
+            (board, PlayerColor::White)
+        });
+        let (movers, _) = self.play_moves(&mut board, turn);
+        let numbers = number_moves(&board, &movers);
+        let mut rounds: Vec<String> = vec![];
+        for (ply, text) in self.moves.iter().enumerate() {
+            if ply > 0 && numbers[ply] == numbers[ply - 1] {
+                let round = rounds.last_mut().expect("A move was written before");
+                round.push(' ');
+                round.push_str(text);
+                continue;
+            }
+            let dots = if board.turn_index(movers[ply]) == 0 {
+                "."
+            } else {
+                "..."
+            };
+            rounds.push(format!("{}{dots} {text}", numbers[ply]));
+        }
+        writeln!(f, "{}", rounds.join(" "))
+    }
+}
+
+/// Today's date in UTC, as `YYYY.MM.DD`
+fn today() -> String {
+    let days = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since_epoch| since_epoch.as_secs() / 86_400);
+    // Converts days since 1970-01-01 to a date, treating years as starting in March
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    format!("{year:04}.{month:02}.{day:02}")
+}
+
+#[test]
+fn written_records_replay() {
+    let mut board = Board::default();
+    let mut record = GameRecord::new(&["random", "random"], BoardConfig::default());
+    let mut turn = PlayerColor::White;
+    for _ in 0..6 {
+        let r#move = board.legal_moves(turn)[7];
+        board.make_legal_move(r#move, turn);
+        record.push(r#move);
+        turn = board.next_player(turn);
+    }
+    let parsed = GameRecord::parse(&record.to_string()).expect("Written records should parse");
+    assert_eq!(parsed, record);
+    assert_eq!(parsed.replay(), Ok(board));
+
+    let illegal = GameRecord::parse("[Fences \"10\"]\n\n1. e2 e8 2. e1 e5").expect("Should parse");
+    assert_eq!(
+        illegal.replay().map_err(|err| err.ply),
+        Err(3),
+        "Black can't move from e8 to e5"
+    );
+
+    let too_many = GameRecord::parse("[Fences \"100\"]\n\n1. e2").expect("Should parse");
+    assert_eq!(
+        too_many.replay().map_err(|err| err.reason),
+        Err(IllegalMoveReason::InvalidPosition(
+            PositionError::InvalidFenceCount("100".to_owned())
+        ))
+    );
+
+    let config = BoardConfig {
+        size: 7,
+        fences: 4,
+        players: 2,
+    };
+    let mut record = GameRecord::new(&["random", "random"], config);
+    record.moves = ["d2", "d6", "a6h", "d5"].map(String::from).to_vec();
+    let parsed = GameRecord::parse(&record.to_string()).expect("Written records should parse");
+    assert_eq!(parsed.header("Size"), Some("7"));
+    let board = parsed.replay().expect("The moves are legal on 7x7");
+    assert_eq!(
+        board.to_position_str(PlayerColor::White),
+        "7x7 d2 d5 a6h 3 4 w"
+    );
+
+    let mut board = Board::new(BoardConfig::with_players(4));
+    let mut record = GameRecord::new(&["random"; 4], BoardConfig::with_players(4));
+    let mut turn = PlayerColor::White;
+    for _ in 0..6 {
+        let r#move = board.legal_moves(turn)[3];
+        board.make_legal_move(r#move, turn);
+        record.push(r#move);
+        turn = board.next_player(turn);
+    }
+    record.set_result(GameResult::Win(PlayerColor::Red));
+    let parsed = GameRecord::parse(&record.to_string()).expect("Written records should parse");
+    assert_eq!(parsed.players(), 4);
+    assert_eq!(parsed.header("Result"), Some("red"));
+    assert_eq!(parsed.replay(), Ok(board));
+}
+
+#[test]
+fn records_started_by_black_number_moves_from_white() {
+    let mut record = GameRecord::new(&["random", "random"], BoardConfig::default());
+    record.set_header("Position", "e1 e9 - 10 10 b");
+    record.moves = ["f3v", "f1h", "d3v"].map(String::from).to_vec();
+    let text = record.to_string();
+    assert!(text.ends_with("\n1... f3v 2. f1h d3v\n"), "{text}");
+    let parsed = GameRecord::parse(&text).expect("Written records should parse");
+    assert_eq!(parsed, record);
+    let board = parsed.replay().expect("The moves are legal");
+    assert_eq!(
+        board.to_position_str(PlayerColor::White),
+        "e1 e9 d3v,f3v,f1h 9 8 w"
+    );
+
+    record.moves.push("e5".to_owned());
+    let error = record.replay().expect_err("White can't move from e1 to e5");
+    assert_eq!(
+        error.to_string().split(':').next(),
+        Some("move 3. `e5` is illegal")
+    );
+}
+
+#[test]
+fn skipped_players_keep_the_move_numbers() {
+    let mut record = GameRecord::new(&["random"; 4], BoardConfig::with_players(4));
+    let board = super::board::invariants::boxed_in_white();
+    record.set_header("Position", &board.to_position_str(PlayerColor::White));
+    record.moves = ["a5h", "e8", "e5h", "g5h", "e9", "c7h"]
+        .map(String::from)
+        .to_vec();
+    let text = record.to_string();
+    assert!(
+        text.ends_with("\n1... a5h e8 e5h 2... g5h e9 c7h\n"),
+        "White is boxed in, so Red starts every move number: {text}"
+    );
+    assert_eq!(GameRecord::parse(&text), Ok(record.clone()));
+
+    record.moves.push("e1".to_owned());
+    let error = record.replay().expect_err("Red can't move from c1 to e1");
+    assert_eq!(
+        error.to_string().split(':').next(),
+        Some("move 3... `e1` is illegal")
+    );
+}
@@ -0,0 +1,428 @@
+//! A game in progress, keeping track of what the board alone doesn't: whose turn it is and the
+//! moves that led to the position, which can be taken back and replayed
+//!
+//! Besides a pawn reaching its goal, a game can end in a draw under its `DrawRules`, so that bots
+//! that shuffle their pawns around don't play forever. Timed games also end when a player runs out
+//! of time on their `Clock`.
+use core::fmt::Display;
+use std::time::Duration;
+
+use super::{clock::Clock, record, Board, IllegalMove, LegalMove, Move, PlayerColor};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Game {
+    board: Board,
+    to_move: PlayerColor,
+    history: Vec<LegalMove>,
+    /// Who made each move in `history`, as players who can't move are skipped
+    movers: Vec<PlayerColor>,
+    /// Moves taken back with `undo`, the next one to redo last
+    undone: Vec<LegalMove>,
+    /// Keys of the positions reached, the starting one first
+    positions: Vec<u64>,
+    draw_rules: DrawRules,
+    /// Undoing and redoing moves leaves the clock alone, like on a real one
+    clock: Option<Clock>,
+    result: Option<GameResult>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameResult {
+    Win(PlayerColor),
+    Draw(DrawReason),
+    /// The player ran out of time, losing the game
+    OutOfTime(PlayerColor),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DrawReason {
+    /// The same position came up `DrawRules::repetitions` times
+    Repetition,
+    /// `DrawRules::max_plies` moves were made without a winner
+    MoveLimit,
+}
+
+/// When a game is declared a draw, where `None` turns a rule off
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DrawRules {
+    /// How many times a position has to come up, with the same side to move, for a draw
+    pub repetitions: Option<usize>,
+    /// How many moves, counting each side's separately, can be made before a draw
+    pub max_plies: Option<usize>,
+}
+
+impl Default for DrawRules {
+    fn default() -> Self {
+        Self {
+            repetitions: Some(3),
+            max_plies: None,
+        }
+    }
+}
+
+impl Display for GameResult {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Win(winner) => write!(f, "{winner} won"),
+            Self::Draw(DrawReason::Repetition) => write!(f, "draw by repetition"),
+            Self::Draw(DrawReason::MoveLimit) => write!(f, "draw by the move limit"),
+            Self::OutOfTime(loser) => write!(f, "{loser} ran out of time"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlayError {
+    GameOver,
+    Illegal(IllegalMove),
+}
+
+impl Display for PlayError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::GameOver => write!(f, "the game is already over"),
+            Self::Illegal(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for PlayError {}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new(Board::default(), PlayerColor::White)
+    }
+}
+
+impl Game {
+    /// A game starting from `board` with `to_move` to move, or the next player who can move if
+    /// they can't
+    pub fn new(board: Board, to_move: PlayerColor) -> Self {
+        let to_move = board.first_able_to_move(to_move);
+        let mut game = Self {
+            positions: vec![board.hash_key(to_move)],
+            board,
+            to_move,
+            history: vec![],
+            movers: vec![],
+            undone: vec![],
+            draw_rules: DrawRules::default(),
+            clock: None,
+            result: None,
+        };
+        game.adjudicate();
+        game
+    }
+
+    pub fn with_draw_rules(mut self, draw_rules: DrawRules) -> Self {
+        self.draw_rules = draw_rules;
+        self.adjudicate();
+        self
+    }
+
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = Some(clock);
+        self
+    }
+
+    pub const fn clock(&self) -> Option<&Clock> {
+        self.clock.as_ref()
+    }
+
+    pub const fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub const fn to_move(&self) -> PlayerColor {
+        self.to_move
+    }
+
+    pub fn history(&self) -> &[LegalMove] {
+        &self.history
+    }
+
+    /// Who made each move in `history`
+    pub fn movers(&self) -> &[PlayerColor] {
+        &self.movers
+    }
+
+    /// The number of the current move, numbered like the moves in game records
+    pub fn move_number(&self) -> usize {
+        let mut movers = self.movers.clone();
+        movers.push(self.to_move);
+        *record::number_moves(&self.board, &movers)
+            .last()
+            .expect("The side to move is numbered")
+    }
+
+    pub const fn result(&self) -> Option<GameResult> {
+        self.result
+    }
+
+    /// The player who won, where running out of time only hands the win to the opponent in
+    /// two-player games
+    pub const fn winner(&self) -> Option<PlayerColor> {
+        match self.result {
+            Some(GameResult::Win(winner)) => Some(winner),
+            Some(GameResult::OutOfTime(loser)) if self.board.players().len() == 2 => {
+                Some(self.board.next_player(loser))
+            }
+            Some(GameResult::OutOfTime(_) | GameResult::Draw(_)) | None => None,
+        }
+    }
+
+    pub const fn is_over(&self) -> bool {
+        self.result.is_some()
+    }
+
+    /// Checks the move and makes it for the side to move
+    pub fn play(&mut self, r#move: Move) -> Result<LegalMove, PlayError> {
+        if self.is_over() {
+            return Err(PlayError::GameOver);
+        }
+        let legal_move = self
+            .board
+            .explain_move(r#move, self.to_move)
+            .map_err(PlayError::Illegal)?;
+        self.play_legal(legal_move)?;
+        Ok(legal_move)
+    }
+
+    /// Makes a move for the side to move, which has to be legal for it in this position, as the
+    /// moves from `legal_moves` are
+    ///
+    /// Once the game is over, no move is made and `PlayError::GameOver` is returned instead.
+    pub fn play_legal(&mut self, r#move: LegalMove) -> Result<(), PlayError> {
+        if self.is_over() {
+            return Err(PlayError::GameOver);
+        }
+        self.undone.clear();
+        self.advance(r#move);
+        Ok(())
+    }
+
+    /// Like `play_legal`, but first charges the side to move for the `elapsed` time the move
+    /// took. If that is more than they had left, they lose on time and the move isn't made.
+    ///
+    /// Returns whether the move was made, which it never is once the game is over.
+    pub fn play_legal_timed(&mut self, r#move: LegalMove, elapsed: Duration) -> bool {
+        if self.is_over() {
+            return false;
+        }
+        if let Some(clock) = &mut self.clock {
+            if !clock.charge(self.to_move, elapsed) {
+                self.result = Some(GameResult::OutOfTime(self.to_move));
+                return false;
+            }
+        }
+        self.play_legal(r#move).is_ok()
+    }
+
+    /// Takes back the last move, returning it
+    ///
+    /// Running out of time isn't something the position shows, so once a game is lost on time
+    /// its moves can't be taken back or replayed anymore.
+    pub fn undo(&mut self) -> Option<LegalMove> {
+        if self.is_lost_on_time() {
+            return None;
+        }
+        let r#move = self.history.pop()?;
+        self.to_move = self.movers.pop().expect("Every move has a mover");
+        self.board.unmake_legal_move(r#move, self.to_move);
+        self.positions.pop();
+        self.adjudicate();
+        self.undone.push(r#move);
+        Some(r#move)
+    }
+
+    /// Makes the last move taken back again, returning it
+    pub fn redo(&mut self) -> Option<LegalMove> {
+        if self.is_lost_on_time() {
+            return None;
+        }
+        let r#move = self.undone.pop()?;
+        self.advance(r#move);
+        Some(r#move)
+    }
+
+    const fn is_lost_on_time(&self) -> bool {
+        matches!(self.result, Some(GameResult::OutOfTime(_)))
+    }
+
+    fn advance(&mut self, r#move: LegalMove) {
+        self.board.make_legal_move(r#move, self.to_move);
+        self.history.push(r#move);
+        self.movers.push(self.to_move);
+        self.to_move = self.board.next_player_to_move(self.to_move);
+        self.positions.push(self.board.hash_key(self.to_move));
+        self.adjudicate();
+    }
+
+    fn adjudicate(&mut self) {
+        let key = self.board.hash_key(self.to_move);
+        let repetitions = self.positions.iter().filter(|&&seen| seen == key).count();
+        self.result = if let Some(winner) = self.board.is_game_won() {
+            Some(GameResult::Win(winner))
+        } else if self
+            .draw_rules
+            .repetitions
+            .is_some_and(|limit| repetitions >= limit)
+        {
+            Some(GameResult::Draw(DrawReason::Repetition))
+        } else if self
+            .draw_rules
+            .max_plies
+            .is_some_and(|limit| self.history.len() >= limit)
+        {
+            Some(GameResult::Draw(DrawReason::MoveLimit))
+        } else {
+            None
+        };
+    }
+}
+
+#[test]
+fn undo_and_redo_retrace_the_game() {
+    let mut game = Game::default();
+    let mut boards = vec![game.board().clone()];
+    for r#move in ["e2", "e8", "e3h", "d7v"] {
+        game.play(r#move.to_owned().try_into().expect("The move is valid"))
+            .expect("The move is legal");
+        boards.push(game.board().clone());
+    }
+    assert_eq!(game.move_number(), 3);
+    assert_eq!(game.to_move(), PlayerColor::White);
+
+    while game.undo().is_some() {
+        boards.pop();
+        assert_eq!(Some(game.board()), boards.last());
+    }
+    assert!(game.history().is_empty());
+    assert_eq!(
+        game.redo().map(|r#move| r#move.to_string()),
+        Some("e2".to_owned())
+    );
+    assert_eq!(game.to_move(), PlayerColor::Black);
+
+    game.play(Move::PawnTo((4, 2)))
+        .expect_err("e7 is two squares away");
+    game.play(Move::PawnTo((3, 0))).expect("d9 is next to e9");
+    assert_eq!(
+        game.redo(),
+        None,
+        "Playing a new move forgets the undone ones"
+    );
+}
+
+#[test]
+fn reaching_the_goal_ends_the_game() {
+    let (board, to_move) =
+        Board::from_position_str("a8 i2 - 0 0 w").expect("The position is valid");
+    let mut game = Game::new(board, to_move);
+    game.play(Move::PawnTo((0, 0))).expect("a9 is White's goal");
+    assert!(game.is_over());
+    assert_eq!(game.winner(), Some(PlayerColor::White));
+    assert_eq!(game.play(Move::PawnTo((7, 8))), Err(PlayError::GameOver));
+    let reply = game.board().legal_pawn_moves(PlayerColor::Black)[0];
+    assert_eq!(game.play_legal(reply), Err(PlayError::GameOver));
+    assert_eq!(game.history().len(), 1);
+    game.undo();
+    assert!(!game.is_over());
+}
+
+#[test]
+fn draws_are_adjudicated() {
+    let shuffle = ["e2", "e8", "e1", "e9"]
+        .map(|r#move| Move::try_from(r#move.to_owned()).expect("The move is valid"));
+
+    let mut game = Game::default();
+    for r#move in shuffle.iter().chain(&shuffle) {
+        game.play(*r#move).expect("The move is legal");
+    }
+    assert_eq!(
+        game.result(),
+        Some(GameResult::Draw(DrawReason::Repetition)),
+        "The starting position came up for the third time"
+    );
+    assert_eq!(game.winner(), None);
+    game.undo();
+    assert_eq!(game.result(), None);
+
+    let mut game = Game::default().with_draw_rules(DrawRules {
+        repetitions: None,
+        max_plies: Some(6),
+    });
+    for r#move in shuffle.iter().chain(&shuffle).take(5) {
+        game.play(*r#move).expect("The move is legal");
+    }
+    assert!(!game.is_over());
+    game.play(shuffle[1]).expect("The move is legal");
+    assert_eq!(game.result(), Some(GameResult::Draw(DrawReason::MoveLimit)));
+}
+
+#[test]
+fn running_out_of_time_loses() {
+    use super::clock::{Increment, TimeControl};
+    let control = TimeControl {
+        base: Duration::from_secs(10),
+        increment: Increment::Fischer(Duration::from_secs(1)),
+    };
+    let mut game = Game::default().with_clock(Clock::new(&[control; 2]));
+    let moves = Board::default().legal_pawn_moves(PlayerColor::White);
+    assert!(game.play_legal_timed(moves[0], Duration::from_secs(4)));
+    assert_eq!(
+        game.clock()
+            .map(|clock| clock.remaining(PlayerColor::White)),
+        Some(Duration::from_secs(7))
+    );
+    let reply = game.board().legal_pawn_moves(PlayerColor::Black)[0];
+    assert!(!game.play_legal_timed(reply, Duration::from_secs(11)));
+    assert_eq!(
+        game.result(),
+        Some(GameResult::OutOfTime(PlayerColor::Black))
+    );
+    assert_eq!(game.winner(), Some(PlayerColor::White));
+    assert_eq!(game.history().len(), 1, "The late move isn't made");
+    assert_eq!(game.undo(), None, "The flag has fallen");
+    assert_eq!(game.redo(), None);
+    assert_eq!(
+        game.result(),
+        Some(GameResult::OutOfTime(PlayerColor::Black))
+    );
+
+    let (board, to_move) =
+        Board::from_position_str("a8 i2 - 0 0 w").expect("The position is valid");
+    let mut game = Game::new(board, to_move).with_clock(Clock::new(&[control; 2]));
+    game.play(Move::PawnTo((0, 0))).expect("a9 is White's goal");
+    let reply = game.board().legal_pawn_moves(PlayerColor::Black)[0];
+    assert!(!game.play_legal_timed(reply, Duration::from_secs(30)));
+    assert_eq!(
+        game.result(),
+        Some(GameResult::Win(PlayerColor::White)),
+        "The game was already over"
+    );
+}
+
+#[test]
+fn players_who_cant_move_are_skipped() {
+    let board = super::board::invariants::boxed_in_white();
+    assert_eq!(
+        Game::new(board.clone(), PlayerColor::White).to_move(),
+        PlayerColor::Red
+    );
+
+    let mut game = Game::new(board, PlayerColor::Green);
+    game.play(Move::try_from("e5h".to_owned()).expect("The move is valid"))
+        .expect("Green has fences left");
+    assert_eq!(game.to_move(), PlayerColor::Red, "White is still boxed in");
+    game.undo();
+    assert_eq!(game.to_move(), PlayerColor::Green);
+
+    let mut game = Game::new(super::board::invariants::boxed_in_white(), PlayerColor::Red);
+    for r#move in ["a5h", "e8", "e5h", "g5h", "e9", "c7h"] {
+        assert_eq!(game.move_number(), game.history().len() / 3 + 1);
+        game.play(Move::try_from(r#move.to_owned()).expect("The move is valid"))
+            .expect("The move is legal");
+    }
+    assert_eq!(game.move_number(), 3, "Every player has moved twice");
+}
@@ -1,86 +1,220 @@
 #![warn(clippy::all, clippy::pedantic, clippy::nursery, clippy::unwrap_used)]
 #![allow(dead_code)]
+mod bench;
+mod cli;
 mod game;
-use game::{fence_move, pawn_move, Board, LegalMove, MoveMakeFail, PlayerColor};
+mod player;
+mod tui;
+use cli::{Command, PlayOptions, PlayerSpec};
+use game::{
+    board::{position::PositionError, render::RenderOptions},
+    clock::{format_time, Clock},
+    record::GameRecord,
+    state::GameResult,
+    Board, BoardConfig, Game, Move, PlayerColor,
+};
+use player::Player;
+use rand::{rngs::StdRng, SeedableRng};
+use std::{path::Path, time::Instant};
 
-fn get_legal_move_using_players_input(board: &Board, turn: PlayerColor) -> LegalMove {
-    loop {
-        let try_into = input_macro::input!("Type in {turn} player's move:").try_into();
-        let the_move = match try_into {
-            Ok(the_move) => the_move,
+/// The players of a game, indexed by player
+type Players = Vec<Box<dyn Player>>;
+
+/// Sets up the game described by `options` and plays its opening moves, returning it with its
+/// players and record, or `None` after printing what is wrong with the options
+fn start_game(options: &PlayOptions) -> Option<(Game, Players, GameRecord)> {
+    use PlayerColor::White;
+    let mut game = match &options.position {
+        Some(position) => match Board::from_position_str(position) {
+            Ok((board, to_move)) => Game::new(board, to_move).with_draw_rules(options.draw_rules),
             Err(err) => {
-                match err {
-                    game::TryIntoMoveError::UnrecognizedChar => println!(
-                        "Couldn't understand the move, because there was an unrecognized character"
-                    ),
-                    game::TryIntoMoveError::UnexpectedEndOfString => println!(
-						"Couldn't understand the move, because more characters were expected to be given"
-					),
-                }
-                continue;
+                println!("The starting position is invalid: {err}");
+                return None;
+            }
+        },
+        None => Game::new(Board::new(options.board), White).with_draw_rules(options.draw_rules),
+    };
+    let mut rng = options
+        .seed
+        .map_or_else(StdRng::from_entropy, StdRng::seed_from_u64);
+    // Indexed by player, in the order of `PlayerColor::ALL`
+    let specs = [options.white, options.black, options.red, options.green];
+    let specs = &specs[..game.board().players().len()];
+    let mut players: Vec<_> = specs
+        .iter()
+        .map(|&spec| {
+            let rng = StdRng::from_rng(&mut rng).expect("StdRng can't fail");
+            player::from_spec(spec, rng, options.render)
+        })
+        .collect();
+    if let Some(controls) = &options.clocks {
+        if controls.len() == players.len() {
+            game = game.with_clock(Clock::new(controls));
+        } else {
+            println!("Every player needs a time control");
+            return None;
+        }
+    }
+    let names: Vec<_> = specs.iter().map(ToString::to_string).collect();
+    let mut record = GameRecord::new(
+        &names.iter().map(String::as_str).collect::<Vec<_>>(),
+        BoardConfig {
+            size: game.board().size(),
+            fences: game.board().fences_left(White),
+            players: players.len(),
+        },
+    );
+    if options.position.is_some() {
+        record.set_header("Position", &game.board().to_position_str(game.to_move()));
+    }
+    for r#move in &options.moves {
+        let parsed = match Move::parse(r#move, game.board().size()) {
+            Ok(parsed) => parsed,
+            Err(err) => {
+                println!("Couldn't understand the opening move `{move}`: {err}");
+                return None;
             }
         };
-        match board.make_move_legal(the_move, turn) {
+        match game.play(parsed) {
+            Ok(legal_move) => {
+                record.push(legal_move);
+                // Nobody chose the opening moves, so every player hears about them
+                for player in &mut players {
+                    player.opponent_moved(game.board(), legal_move);
+                }
+            }
             Err(err) => {
-                match err {
-                    MoveMakeFail::PawnMoveFail(pawn_move::Fail::PathObstructed) => {
-                        println!("Couldn't move the pawn, because the chosen path was obstructed")
-                    }
-
-                    MoveMakeFail::PawnMoveFail(pawn_move::Fail::NoSecondary) => println!(
-						"Couldn't move the pawn, because secondary direction was required but not provided"
-					),
-                    MoveMakeFail::PawnMoveFail(pawn_move::Fail::InvalidSecondary) => println!(
-						"Couldn't move the pawn, because secondary direction was not perpendicular to the primary direction"
-					),
-                    MoveMakeFail::AddFenceMove(fence_move::Fail::Collides) => println!(
-                        "Couldn't add the fence there, because it would collide with another fence"
-                    ),
-                    MoveMakeFail::AddFenceMove(fence_move::Fail::NoPathRemaining) => println!(
-						"Couldn't add the fence there, because it would leave no path for at least one of the pawns"
-					),
-                    MoveMakeFail::AddFenceMove(fence_move::Fail::NoFencesRemaining) => {
-                        println!("Couldn't add the fence, because there are no fences left for you")
-                    }
-                };
-                continue;
+                println!("The opening move `{move}` is not legal: {err}");
+                return None;
             }
-            Ok(the_move) => return the_move,
         }
     }
+    Some((game, players, record))
 }
 
-fn game_loop() {
-    use PlayerColor::{Black, White};
-    let mut turn = White;
-    let mut board = Board::default();
-    let mut i = 0;
-    loop {
-        // println!("{board}");
-        println!("It's {turn} player's move! (turn {i})");
-        #[allow(unreachable_patterns, clippy::match_same_arms)]
-        let r#move = match turn {
-            White => board.find_best_move(turn),
-            White => get_legal_move_using_players_input(&board, turn),
-            Black => board.find_best_move(turn),
-            Black => get_legal_move_using_players_input(&board, turn),
-        };
-        board.make_legal_move(r#move, turn);
-        println!("Made move {move:?}");
-        if let Some(player) = board.is_game_won() {
-            println!("{board}");
-            println!("{player:?} player won!");
+fn game_loop(options: &PlayOptions) {
+    let Some((mut game, mut players, mut record)) = start_game(options) else {
+        return;
+    };
+    if options.tui {
+        // People move with the cursor, so only the bots are asked for moves
+        let specs = [options.white, options.black, options.red, options.green];
+        let humans = specs.map(|spec| spec == PlayerSpec::Human);
+        if let Err(err) = tui::play(&mut game, &mut players, &humans, &mut record) {
+            println!("The terminal can't be used full-screen: {err}");
+        }
+    } else {
+        play_in_terminal(&mut game, &mut players, &mut record);
+    }
+    if let Some(result) = game.result() {
+        print_result(&game, result, options.render);
+        for player in &mut players {
+            player.game_ended(game.board(), result);
+        }
+        record.set_result(result);
+    }
+    if let Some(path) = &options.record {
+        if let Err(err) = std::fs::write(path, record.to_string()) {
+            eprintln!("Couldn't save the game record to {}: {err}", path.display());
+        }
+    }
+}
+
+/// Plays the game out, printing the moves as they are made
+fn play_in_terminal(game: &mut Game, players: &mut [Box<dyn Player>], record: &mut GameRecord) {
+    while !game.is_over() {
+        let turn = game.to_move();
+        println!("It's {turn} player's move! (turn {})", game.history().len());
+        let player = players[turn as usize].as_mut();
+        if let Some(clock) = game.clock() {
+            player.clock_started(clock, turn);
+        }
+        let start = Instant::now();
+        let r#move = player.choose_move(game.board(), turn, game.history());
+        if !game.play_legal_timed(r#move, start.elapsed()) {
             break;
         }
-        turn = match turn {
-            White => Black,
-            Black => White,
-        };
-        i += 1;
+        record.push(r#move);
+        match game.clock() {
+            Some(clock) => println!(
+                "Made move {move} ({} left)",
+                format_time(clock.remaining(turn))
+            ),
+            None => println!("Made move {move}"),
+        }
+        for (idx, other) in players.iter_mut().enumerate() {
+            if idx != turn as usize {
+                other.opponent_moved(game.board(), r#move);
+            }
+        }
     }
 }
 
+fn print_result(game: &Game, result: GameResult, render: RenderOptions) {
+    println!("{}", game.board().render(render));
+    match result {
+        GameResult::Win(winner) => println!("{winner:?} player won!"),
+        GameResult::Draw(_) => println!("The game is a {result}!"),
+        GameResult::OutOfTime(loser) => match game.winner() {
+            Some(winner) => println!("{loser:?} player ran out of time, {winner:?} player won!"),
+            None => println!("{loser:?} player ran out of time, nobody won!"),
+        },
+    }
+}
+
+fn replay(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let record = GameRecord::parse(&std::fs::read_to_string(path)?)?;
+    for (name, value) in &record.headers {
+        println!("{name}: {value}");
+    }
+    let board = record.replay()?;
+    println!("{}", board.render(RenderOptions::detect()));
+    println!("Replayed {} moves", record.moves.len());
+    Ok(())
+}
+fn perft(depth: u32, position: Option<&str>, divide: bool) -> Result<(), PositionError> {
+    let (board, player) = position.map_or_else(
+        || Ok((Board::default(), PlayerColor::White)),
+        Board::from_position_str,
+    )?;
+    let start = Instant::now();
+    let count = if divide {
+        let divided = board.perft_divide(player, depth);
+        for (r#move, count) in &divided {
+            println!("{move}: {count}");
+        }
+        divided.iter().map(|(_, count)| count).sum()
+    } else {
+        board.perft(player, depth)
+    };
+    println!("Total: {count} ({:?})", start.elapsed());
+    Ok(())
+}
+
 fn main() {
-    game_loop();
-    // println!("{}",Board::default());
+    match cli::parse_args(std::env::args().skip(1)) {
+        Ok(Command::Play(options)) => game_loop(&options),
+        Ok(Command::Replay(path)) => {
+            if let Err(err) = replay(&path) {
+                eprintln!("error: {err}");
+                std::process::exit(1);
+            }
+        }
+        Ok(Command::Bench { positions, seed }) => bench::run(positions, seed),
+        Ok(Command::Perft {
+            depth,
+            position,
+            divide,
+        }) => {
+            if let Err(err) = perft(depth, position.as_deref(), divide) {
+                eprintln!("error: {err}");
+                std::process::exit(1);
+            }
+        }
+        Ok(Command::Help) => println!("{}", cli::USAGE),
+        Err(err) => {
+            eprintln!("error: {err}\n\n{}", cli::USAGE);
+            std::process::exit(2);
+        }
+    }
 }
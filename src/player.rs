@@ -0,0 +1,158 @@
+use std::time::Duration;
+
+use rand::{rngs::StdRng, seq::SliceRandom};
+
+use crate::cli::PlayerSpec;
+use crate::game::{
+    board::{
+        mcts::MctsConfig, render::RenderOptions, search_and_evaluation::SearchLimits,
+        transposition_table::TranspositionTable,
+    },
+    clock::{format_time, Clock},
+    state::GameResult,
+    Board, LegalMove, Move, PlayerColor,
+};
+
+/// Anything that can take part in a game, be it a person, a bot or a remote agent
+pub trait Player {
+    /// Picks the move to make as `color`, after the moves in `history` led to `board`, which
+    /// is only asked of players who can move
+    fn choose_move(
+        &mut self,
+        board: &Board,
+        color: PlayerColor,
+        history: &[LegalMove],
+    ) -> LegalMove;
+
+    /// Called after another player made `r#move`, which led to `board`
+    fn opponent_moved(&mut self, _board: &Board, _move: LegalMove) {}
+
+    /// Called before `choose_move` in timed games, with the clock as it is when the move starts
+    fn clock_started(&mut self, _clock: &Clock, _color: PlayerColor) {}
+
+    /// Called once the game is over
+    fn game_ended(&mut self, _board: &Board, _result: GameResult) {}
+}
+
+/// Creates the player described by `spec`, using `rng` for its randomness and drawing the board
+/// for people with `render`
+pub fn from_spec(spec: PlayerSpec, rng: StdRng, render: RenderOptions) -> Box<dyn Player> {
+    match spec {
+        PlayerSpec::Human => Box::new(HumanPlayer {
+            render,
+            time_left: None,
+        }),
+        PlayerSpec::Random => Box::new(RandomPlayer { rng }),
+        PlayerSpec::AlphaBeta { depth, time } => Box::new(AlphaBetaPlayer::new(SearchLimits {
+            depth,
+            time,
+            ..SearchLimits::default()
+        })),
+        PlayerSpec::Mcts(config) => Box::new(MctsPlayer { config, rng }),
+    }
+}
+
+fn get_legal_move_using_players_input(board: &Board, turn: PlayerColor) -> LegalMove {
+    loop {
+        let input =
+            input_macro::input!("Type in {turn} player's move (like `e2`, `e3h` or `w`, `-a1`):");
+        let the_move = match Move::parse(&input, board.size()) {
+            Ok(the_move) => the_move,
+            Err(err) => {
+                println!("Couldn't understand the move: {err}");
+                continue;
+            }
+        };
+        match board.explain_move(the_move, turn) {
+            Ok(the_move) => return the_move,
+            Err(err) => println!("Couldn't make the move: {err}"),
+        }
+    }
+}
+
+/// Reads moves from standard input
+#[derive(Debug, Default)]
+pub struct HumanPlayer {
+    pub render: RenderOptions,
+    /// Time left on the clock, shown before asking for a move
+    time_left: Option<Duration>,
+}
+
+impl Player for HumanPlayer {
+    fn choose_move(&mut self, board: &Board, color: PlayerColor, _: &[LegalMove]) -> LegalMove {
+        println!("{}", board.render(self.render));
+        if let Some(time_left) = self.time_left {
+            println!("You have {} left", format_time(time_left));
+        }
+        get_legal_move_using_players_input(board, color)
+    }
+
+    fn clock_started(&mut self, clock: &Clock, color: PlayerColor) {
+        self.time_left = Some(clock.remaining(color));
+    }
+}
+
+#[derive(Debug)]
+pub struct RandomPlayer {
+    pub rng: StdRng,
+}
+
+impl Player for RandomPlayer {
+    fn choose_move(&mut self, board: &Board, color: PlayerColor, _: &[LegalMove]) -> LegalMove {
+        *board
+            .legal_moves(color)
+            .choose(&mut self.rng)
+            .expect("Only players who can move are asked for a move")
+    }
+}
+
+/// Plays the best move found by alpha-beta search, keeping its transposition table between moves
+#[derive(Debug)]
+pub struct AlphaBetaPlayer {
+    pub limits: SearchLimits,
+    table: TranspositionTable,
+    /// Most time the next move can take in a timed game, on top of `limits`
+    budget: Option<Duration>,
+}
+
+impl AlphaBetaPlayer {
+    pub fn new(limits: SearchLimits) -> Self {
+        Self {
+            limits,
+            table: TranspositionTable::default(),
+            budget: None,
+        }
+    }
+}
+
+impl Player for AlphaBetaPlayer {
+    fn choose_move(&mut self, board: &Board, color: PlayerColor, _: &[LegalMove]) -> LegalMove {
+        let limits = SearchLimits {
+            time: self.limits.time.into_iter().chain(self.budget).min(),
+            ..self.limits.clone()
+        };
+        board
+            .search_with_table(color, &limits, &mut self.table)
+            .best_move
+    }
+
+    fn clock_started(&mut self, clock: &Clock, color: PlayerColor) {
+        self.budget = Some(clock.move_budget(color));
+    }
+}
+
+#[derive(Debug)]
+pub struct MctsPlayer {
+    pub config: MctsConfig,
+    pub rng: StdRng,
+}
+
+impl Player for MctsPlayer {
+    fn choose_move(&mut self, board: &Board, color: PlayerColor, _: &[LegalMove]) -> LegalMove {
+        board.mcts_best_move(color, &self.config, &mut self.rng)
+    }
+
+    fn clock_started(&mut self, clock: &Clock, color: PlayerColor) {
+        self.config.time = Some(clock.move_budget(color));
+    }
+}
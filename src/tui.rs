@@ -0,0 +1,574 @@
+//! A full-screen terminal interface, where people play by moving a cursor over the board
+//!
+//! The arrow keys move the cursor, `Tab` switches between moving the pawn and placing a fence,
+//! `Space` turns the fence around and `Enter` makes the move. The squares the pawn can move to
+//! are highlighted, and a fence about to be placed is shown green where it can go and red where
+//! it can't. Bots make their moves in between, with the screen showing who is thinking.
+//! `q`, `Esc` or `Ctrl-C` leaves the game, which is also noticed after each bot move, so that
+//! games between bots can be left too.
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+use crossterm::{
+    cursor,
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute, queue,
+    style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor},
+    terminal,
+};
+
+use crate::game::{
+    clock::format_time,
+    notation,
+    record::{number_moves, GameRecord},
+    Axis, Board, Game, LegalMove, Move, PlayerColor,
+};
+use crate::player::Player;
+
+/// How often the screen is redrawn while waiting for a key, so that the clocks keep ticking
+const REFRESH: Duration = Duration::from_millis(200);
+/// Columns left of the board, which hold the row numbers
+const LEFT_MARGIN: usize = 3;
+const HELP: &str = "arrows: move  tab: pawn or fence  space: turn the fence  enter: play  q: quit";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Style {
+    fg: Color,
+    bg: Color,
+}
+
+impl Style {
+    const PLAIN: Self = Self::fg(Color::Reset);
+
+    const fn fg(fg: Color) -> Self {
+        Self {
+            fg,
+            bg: Color::Reset,
+        }
+    }
+}
+
+const FENCE: Style = Style::fg(Color::Yellow);
+const LEGAL_FENCE: Style = Style::fg(Color::Green);
+const ILLEGAL_FENCE: Style = Style::fg(Color::Red);
+/// A square the pawn to move can go to
+const DESTINATION: Style = Style {
+    fg: Color::Reset,
+    bg: Color::DarkGreen,
+};
+const CURSOR: Style = Style {
+    fg: Color::Black,
+    bg: Color::Grey,
+};
+
+const fn pawn_style(player: PlayerColor) -> Style {
+    Style::fg(match player {
+        PlayerColor::White => Color::White,
+        PlayerColor::Black => Color::Blue,
+        PlayerColor::Red => Color::Red,
+        PlayerColor::Green => Color::Green,
+    })
+}
+
+/// What is shown on the screen, as rows of characters with their colors
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Frame {
+    cells: Vec<Vec<(char, Style)>>,
+}
+
+impl Frame {
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            cells: vec![vec![(' ', Style::PLAIN); width]; height],
+        }
+    }
+
+    /// Writes `text` from column `x` of row `y` on, cutting off whatever doesn't fit
+    fn text(&mut self, (x, y): (usize, usize), text: &str, style: Style) {
+        if let Some(row) = self.cells.get_mut(y) {
+            for (cell, c) in row.iter_mut().skip(x).zip(text.chars()) {
+                *cell = (c, style);
+            }
+        }
+    }
+
+    /// Gives the cell at column `x` of row `y` the background of `style`, or all of `style` if
+    /// it is blank
+    fn highlight(&mut self, (x, y): (usize, usize), style: Style) {
+        if let Some(cell) = self.cells.get_mut(y).and_then(|row| row.get_mut(x)) {
+            if cell.0 == ' ' || style.fg != Color::Reset {
+                cell.1 = style;
+            } else {
+                cell.1.bg = style.bg;
+            }
+        }
+    }
+
+    fn row_text(&self, y: usize) -> String {
+        self.cells[y].iter().map(|&(c, _)| c).collect()
+    }
+
+    fn write(&self, out: &mut impl Write) -> io::Result<()> {
+        let mut current = None;
+        for (y, row) in self.cells.iter().enumerate() {
+            queue!(out, cursor::MoveTo(0, u16::try_from(y).unwrap_or(u16::MAX)))?;
+            for &(c, style) in row {
+                if current != Some(style) {
+                    queue!(
+                        out,
+                        SetForegroundColor(style.fg),
+                        SetBackgroundColor(style.bg)
+                    )?;
+                    current = Some(style);
+                }
+                queue!(out, Print(c))?;
+            }
+        }
+        queue!(out, ResetColor)?;
+        out.flush()
+    }
+}
+
+/// Where a person is about to move, either a square for their pawn or a slot for a fence
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Cursor {
+    pos: (usize, usize),
+    fence: Option<Axis>,
+}
+
+impl Cursor {
+    /// Moves the cursor by the arrow (or `hjkl`) key `code`, staying on a board of `size`
+    fn step(&mut self, code: KeyCode, size: usize) {
+        let last = if self.fence.is_some() {
+            size - 2
+        } else {
+            size - 1
+        };
+        let (x, y) = &mut self.pos;
+        match code {
+            KeyCode::Left | KeyCode::Char('h') => *x = x.saturating_sub(1),
+            KeyCode::Right | KeyCode::Char('l') => *x = (*x + 1).min(last),
+            KeyCode::Up | KeyCode::Char('k') => *y = y.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => *y = (*y + 1).min(last),
+            _ => {}
+        }
+    }
+
+    /// Switches between moving the pawn and placing a fence, whose slots don't reach the last
+    /// column and row
+    fn toggle_fence(&mut self, size: usize) {
+        if self.fence.is_some() {
+            self.fence = None;
+        } else {
+            self.pos = (self.pos.0.min(size - 2), self.pos.1.min(size - 2));
+            self.fence = Some(Axis::Horizontal);
+        }
+    }
+
+    fn turn_fence(&mut self) {
+        self.fence = self.fence.map(|axis| match axis {
+            Axis::Horizontal => Axis::Vertical,
+            Axis::Vertical => Axis::Horizontal,
+        });
+    }
+
+    const fn r#move(self) -> Move {
+        match self.fence {
+            Some(axis) => Move::PlaceFence(axis, self.pos),
+            None => Move::PawnTo(self.pos),
+        }
+    }
+}
+
+/// Everything that goes on the screen
+struct View<'a> {
+    game: &'a Game,
+    /// The cursor of the person to move, if it is a person's turn
+    cursor: Option<Cursor>,
+    status: &'a str,
+    /// How long the side to move has been thinking, which isn't on the clock yet
+    elapsed: Duration,
+}
+
+/// Screen column and row of the middle of the square at `(x, y)`
+const fn square_cell((x, y): (usize, usize)) -> (usize, usize) {
+    (LEFT_MARGIN + 4 * x + 2, 2 * y + 1)
+}
+
+/// Screen cells covered by a fence in the slot at `(x, y)`, with the character drawn on them
+fn fence_cells(axis: Axis, (x, y): (usize, usize)) -> Vec<((usize, usize), char)> {
+    let (column, row) = (LEFT_MARGIN + 4 * x, 2 * y);
+    match axis {
+        Axis::Horizontal => (1..=7).map(|dx| ((column + dx, row + 2), '━')).collect(),
+        Axis::Vertical => (1..=3).map(|dy| ((column + 4, row + dy), '┃')).collect(),
+    }
+}
+
+/// The grid line character at column `x` and row `y` of a board with `size` squares a side,
+/// counting from its top left corner
+const fn grid_char((x, y): (usize, usize), size: usize) -> char {
+    let (column, row) = (x / 4, y / 2);
+    match (x % 4 == 0, y % 2 == 0) {
+        (true, true) => {
+            let top = row == 0;
+            let bottom = row == size;
+            match (column == 0, column == size) {
+                (true, _) if top => '╭',
+                (_, true) if top => '╮',
+                (true, _) if bottom => '╰',
+                (_, true) if bottom => '╯',
+                (true, _) => '├',
+                (_, true) => '┤',
+                _ if top => '┬',
+                _ if bottom => '┴',
+                _ => '┼',
+            }
+        }
+        (false, true) => '─',
+        (true, false) => '│',
+        (false, false) => ' ',
+    }
+}
+
+fn draw_board(frame: &mut Frame, board: &Board, turn: PlayerColor, cursor: Option<Cursor>) {
+    let size = board.size();
+    for y in 0..=2 * size {
+        for x in 0..=4 * size {
+            let c = grid_char((x, y), size).to_string();
+            frame.text((LEFT_MARGIN + x, y), &c, Style::PLAIN);
+        }
+    }
+    for y in 0..size {
+        frame.text((0, 2 * y + 1), &format!("{:>2}", size - y), Style::PLAIN);
+        for x in 0..size {
+            if let Some(player) = board.pawn_at((x, y)) {
+                let letter = player.letter().to_ascii_uppercase().to_string();
+                frame.text(square_cell((x, y)), &letter, pawn_style(player));
+            }
+        }
+    }
+    for x in 0..size {
+        let file = notation::file_char(x).to_string();
+        frame.text((square_cell((x, 0)).0, 2 * size + 1), &file, Style::PLAIN);
+    }
+    for y in 0..size - 1 {
+        for x in 0..size - 1 {
+            if let Some(axis) = board.fence_at((x, y)) {
+                for (cell, c) in fence_cells(axis, (x, y)) {
+                    frame.text(cell, &c.to_string(), FENCE);
+                }
+            }
+        }
+    }
+
+    let Some(cursor) = cursor else {
+        return;
+    };
+    let highlight_square = |frame: &mut Frame, pos, style| {
+        let (column, row) = square_cell(pos);
+        for x in column - 1..=column + 1 {
+            frame.highlight((x, row), style);
+        }
+    };
+    for r#move in board.legal_pawn_moves(turn) {
+        if let Some(pos) = r#move.destination() {
+            highlight_square(frame, pos, DESTINATION);
+        }
+    }
+    match cursor.fence {
+        None => highlight_square(frame, cursor.pos, CURSOR),
+        Some(axis) => {
+            let style = if board.is_fence_move_legal(turn, axis, cursor.pos).is_ok() {
+                LEGAL_FENCE
+            } else {
+                ILLEGAL_FENCE
+            };
+            for (cell, c) in fence_cells(axis, cursor.pos) {
+                frame.text(cell, &c.to_string(), style);
+            }
+        }
+    }
+}
+
+/// The players with their fences and clocks, and below them the moves made so far, from
+/// column `x` on with room for `height` rows
+fn draw_panels(frame: &mut Frame, view: &View, x: usize, height: usize) {
+    let (game, board) = (view.game, view.game.board());
+    frame.text((x, 0), "Players", Style::PLAIN);
+    for (idx, &player) in board.players().iter().enumerate() {
+        let row = idx + 1;
+        let to_move = player == game.to_move() && !game.is_over();
+        frame.text((x, row), if to_move { "▶" } else { " " }, Style::PLAIN);
+        let letter = player.letter().to_ascii_uppercase().to_string();
+        frame.text((x + 2, row), &letter, pawn_style(player));
+        let name = format!("{player:?}");
+        let fences = format!("{name:<6} {:>2} fences", board.fences_left(player));
+        frame.text((x + 4, row), &fences, Style::PLAIN);
+        if let Some(clock) = game.clock() {
+            let mut remaining = clock.remaining(player);
+            if to_move {
+                remaining = remaining.saturating_sub(view.elapsed);
+            }
+            frame.text((x + 22, row), &format_time(remaining), Style::PLAIN);
+        }
+    }
+
+    let top = board.players().len() + 2;
+    frame.text((x, top), "Moves", Style::PLAIN);
+    // Each move goes in its mover's place in the turn order, with `...` standing in for
+    // those who didn't move, as when someone else started or a player was skipped
+    let mut lines: Vec<String> = vec![];
+    let mut round = vec![];
+    let numbers = number_moves(board, game.movers());
+    for (idx, (r#move, &mover)) in game.history().iter().zip(game.movers()).enumerate() {
+        if idx > 0 && numbers[idx] != numbers[idx - 1] {
+            lines.push(format!("{:>3}. {}", numbers[idx - 1], round.join(" ")));
+            round.clear();
+        }
+        round.resize(board.turn_index(mover), "...".to_owned());
+        round.push(r#move.to_string());
+    }
+    if let Some(&number) = numbers.last() {
+        lines.push(format!("{number:>3}. {}", round.join(" ")));
+    }
+    let room = height.saturating_sub(top + 1);
+    for (row, line) in (top + 1..).zip(&lines[lines.len().saturating_sub(room)..]) {
+        frame.text((x, row), line, Style::PLAIN);
+    }
+}
+
+fn draw(view: &View, width: usize, height: usize) -> Frame {
+    let mut frame = Frame::new(width, height);
+    let (board, turn) = (view.game.board(), view.game.to_move());
+    let size = board.size();
+    draw_board(&mut frame, board, turn, view.cursor);
+    draw_panels(&mut frame, view, LEFT_MARGIN + 4 * size + 4, 2 * size + 2);
+
+    let status = match view.cursor {
+        Some(Cursor {
+            pos,
+            fence: Some(axis),
+        }) => match board.is_fence_move_legal(turn, axis, pos) {
+            Err(fail) => format!("That fence can't be placed: {fail}"),
+            Ok(()) => view.status.to_owned(),
+        },
+        _ => view.status.to_owned(),
+    };
+    frame.text((0, 2 * size + 3), &status, Style::PLAIN);
+    if view.cursor.is_some() {
+        frame.text((0, 2 * size + 4), HELP, Style::PLAIN);
+    }
+    frame
+}
+
+/// The terminal in raw mode on the alternate screen, which is put back the way it was when this
+/// is dropped
+struct Terminal {
+    out: io::Stdout,
+}
+
+impl Terminal {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        let mut out = io::stdout();
+        execute!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(Self { out })
+    }
+
+    fn draw(&mut self, view: &View) -> io::Result<()> {
+        let (width, height) = terminal::size()?;
+        draw(view, width.into(), height.into()).write(&mut self.out)
+    }
+
+    /// Waits up to `REFRESH` for a key to be pressed
+    fn key() -> io::Result<Option<KeyEvent>> {
+        if !event::poll(REFRESH)? {
+            return Ok(None);
+        }
+        Ok(match event::read()? {
+            Event::Key(key) if key.kind == KeyEventKind::Press => Some(key),
+            _ => None,
+        })
+    }
+
+    /// Throws away the keys pressed since they were last read, which were typed while a bot was
+    /// thinking and aren't meant for what comes next, returning whether one of them was to quit
+    fn discard_keys() -> io::Result<bool> {
+        let mut quit = false;
+        while event::poll(Duration::ZERO)? {
+            if let Event::Key(key) = event::read()? {
+                quit |= key.kind == KeyEventKind::Press && is_quit(key);
+            }
+        }
+        Ok(quit)
+    }
+
+    /// Lets the person to move pick their move with the cursor, returning `None` if they quit
+    fn choose_move(&mut self, game: &Game, status: &mut String) -> io::Result<Option<LegalMove>> {
+        let (board, turn) = (game.board(), game.to_move());
+        let mut cursor = Cursor {
+            pos: board.pawn_pos(turn),
+            fence: None,
+        };
+        let start = Instant::now();
+        loop {
+            if game
+                .clock()
+                .is_some_and(|clock| start.elapsed() >= clock.remaining(turn))
+            {
+                // Any move will do, the clock turns it down and ends the game
+                return Ok(board.legal_moves(turn).first().copied());
+            }
+            self.draw(&View {
+                game,
+                cursor: Some(cursor),
+                status,
+                elapsed: start.elapsed(),
+            })?;
+            let Some(key) = Self::key()? else {
+                continue;
+            };
+            if is_quit(key) {
+                return Ok(None);
+            }
+            match key.code {
+                KeyCode::Tab | KeyCode::Char('f') => cursor.toggle_fence(board.size()),
+                KeyCode::Char(' ' | 'r') => cursor.turn_fence(),
+                KeyCode::Enter => match board.explain_move(cursor.r#move(), turn) {
+                    Ok(r#move) => return Ok(Some(r#move)),
+                    Err(err) => *status = format!("Couldn't make the move: {err}"),
+                },
+                code => cursor.step(code, board.size()),
+            }
+        }
+    }
+}
+
+/// Whether `key` is `q`, `Esc` or `Ctrl-C`, as raw mode keeps `Ctrl-C` from stopping the program
+const fn is_quit(key: KeyEvent) -> bool {
+    match key.code {
+        KeyCode::Char('q') | KeyCode::Esc => true,
+        KeyCode::Char('c') => key.modifiers.contains(KeyModifiers::CONTROL),
+        _ => false,
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        // Nothing more can be done if the terminal can't be put back
+        let _ = execute!(self.out, cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Plays the game out on the full screen, where the players marked in `humans` move with the
+/// cursor and the others through `players`, returning once the game is over or someone quits
+pub fn play(
+    game: &mut Game,
+    players: &mut [Box<dyn Player>],
+    humans: &[bool],
+    record: &mut GameRecord,
+) -> io::Result<()> {
+    let mut terminal = Terminal::enter()?;
+    let mut status = String::new();
+    while !game.is_over() {
+        let turn = game.to_move();
+        let start = Instant::now();
+        let r#move = if humans[turn as usize] {
+            match terminal.choose_move(game, &mut status)? {
+                Some(r#move) => r#move,
+                None => return Ok(()),
+            }
+        } else {
+            status = format!("{turn} is thinking...");
+            terminal.draw(&View {
+                game,
+                cursor: None,
+                status: &status,
+                elapsed: Duration::ZERO,
+            })?;
+            let player = players[turn as usize].as_mut();
+            if let Some(clock) = game.clock() {
+                player.clock_started(clock, turn);
+            }
+            let r#move = player.choose_move(game.board(), turn, game.history());
+            if Terminal::discard_keys()? {
+                return Ok(());
+            }
+            r#move
+        };
+        if !game.play_legal_timed(r#move, start.elapsed()) {
+            break;
+        }
+        record.push(r#move);
+        status = format!("{turn} played {move}");
+        for (idx, other) in players.iter_mut().enumerate() {
+            if idx != turn as usize {
+                other.opponent_moved(game.board(), r#move);
+            }
+        }
+    }
+    if let Some(result) = game.result() {
+        status = format!("The game is over, {result}! Press any key to leave.");
+        terminal.draw(&View {
+            game,
+            cursor: None,
+            status: &status,
+            elapsed: Duration::ZERO,
+        })?;
+        Terminal::discard_keys()?;
+        while Terminal::key()?.is_none() {}
+    }
+    Ok(())
+}
+
+#[test]
+fn highlights_moves_and_fence_previews() {
+    let game = Game::default();
+    let mut view = View {
+        game: &game,
+        cursor: Some(Cursor {
+            pos: (4, 8),
+            fence: None,
+        }),
+        status: "",
+        elapsed: Duration::ZERO,
+    };
+    let frame = draw(&view, 100, 30);
+    let square = |frame: &Frame, pos| {
+        let (x, y) = square_cell(pos);
+        frame.cells[y][x]
+    };
+    assert_eq!(square(&frame, (4, 8)), ('W', CURSOR));
+    assert_eq!(
+        square(&frame, (4, 0)),
+        ('B', pawn_style(PlayerColor::Black))
+    );
+    for pos in [(3, 8), (5, 8), (4, 7)] {
+        assert_eq!(square(&frame, pos), (' ', DESTINATION));
+    }
+    assert_eq!(square(&frame, (4, 6)), (' ', Style::PLAIN));
+    assert!(frame.row_text(17).starts_with(" 1 │"));
+    assert!(frame.row_text(19).starts_with("     a   b   c"));
+
+    // The fences leave White only the way along the bottom row to the last column
+    let (board, to_move) =
+        Board::from_position_str("e1 e9 a1h,c1h,e1h,g1h 8 10 w").expect("The position is valid");
+    let game = Game::new(board, to_move);
+    view.game = &game;
+    view.cursor = Some(Cursor {
+        pos: (7, 7),
+        fence: Some(Axis::Vertical),
+    });
+    let frame = draw(&view, 100, 30);
+    let (x, y) = square_cell((7, 7));
+    assert_eq!(frame.cells[y][x + 2], ('┃', ILLEGAL_FENCE));
+    assert!(frame.row_text(21).contains("no path to its goal"));
+    view.cursor = Some(Cursor {
+        pos: (0, 0),
+        fence: Some(Axis::Horizontal),
+    });
+    let frame = draw(&view, 100, 30);
+    assert_eq!(frame.cells[2][LEFT_MARGIN + 1], ('━', LEGAL_FENCE));
+    assert!(frame.row_text(1).contains("▶ W White   8 fences"));
+}